@@ -0,0 +1,80 @@
+//! Benchmarks for rendering large tables.
+//!
+//! Run with `cargo bench`. Most real-world data is ASCII, which takes a fast path
+//! when measuring and truncating cells. To show what that saves, each benchmark runs on
+//! the same ASCII text, and on that text with one non-ASCII character appended, which
+//! takes the slow path.
+
+use std::io;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use tablestream::{col, Alignment, Stream};
+
+struct Row {
+    name: String,
+    description: String,
+    count: u32,
+}
+
+const DESCRIPTION: &str = "A fairly long description of a city. ";
+
+/// Appended to text to make it take the slow path.
+const NON_ASCII: &str = "é";
+
+fn rows(suffix: &str, count: usize) -> Vec<Row> {
+    (0..count).map(|i| Row {
+        name: format!("Shanghai {}", i),
+        description: DESCRIPTION.repeat(i % 5 + 1) + suffix,
+        count: i as u32,
+    }).collect()
+}
+
+fn render(rows: &[Row]) -> io::Result<()> {
+    let mut stream = Stream::new(io::sink(), vec![
+        col!(Row: .name).header("Name"),
+        col!(Row: .description).header("Description"),
+        col!(Row: .count).header("Count").right(),
+    ]).max_width(80);
+
+    for row in rows {
+        stream.row(Row {
+            name: row.name.clone(),
+            description: row.description.clone(),
+            count: row.count,
+        })?;
+    }
+    stream.finish()?;
+    Ok(())
+}
+
+fn bench_render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    for count in [100, 10_000] {
+        for (name, suffix) in [("ascii", ""), ("non-ascii", NON_ASCII)] {
+            let rows = rows(suffix, count);
+            group.bench_with_input(BenchmarkId::new(name, count), &rows, |b, rows| {
+                b.iter(|| render(black_box(rows)).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Measuring and truncating a single value, via the public `Alignment::pad`.
+fn bench_measure(c: &mut Criterion) {
+    let mut group = c.benchmark_group("measure");
+    let ascii = DESCRIPTION.repeat(3);
+    let non_ascii = ascii.clone() + NON_ASCII;
+    for (name, text) in [("ascii", &ascii), ("non-ascii", &non_ascii)] {
+        // Wide enough to fit, and too narrow, so that the value is truncated:
+        for width in [200, 40] {
+            group.bench_with_input(BenchmarkId::new(name, width), text, |b, text| {
+                b.iter(|| Alignment::Left.pad(black_box(text), width))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_render, bench_measure);
+criterion_main!(benches);
@@ -2,51 +2,46 @@ use std::io::{self, stdout};
 
 
 use clap::Parser;
-use tablestream::{Column, Stream, col};
+use tablestream::{Column, Format, NumberFormat, Stream, col};
 
 fn main() -> io::Result<()> {
 
     let opts = Opts::parse();
 
-    let stdout = stdout();
-    let mut handle = stdout.lock();
 
     let mut cols = vec![
-        Column::new(|f, c: &City| write!(f, "{}", &c.name)).header("City"),
-        col!(City: .country).header("Country"),
+        Column::new(|f, c: &City| write!(f, "{}", &c.name)).header("City").footer_text("Total"),
+        col!(City: .country).header("Country").footer_count(),
         
     ];
 
     if opts.format_pop {
-        cols.push(col!(City: "{:.2e}", .population).header("Population").right());
+        cols.push(Column::typed(|c: &City| c.population, NumberFormat::default().thousands(',')).header("Population").footer_sum());
     } else {
-        cols.push(col!(City: .population).header("Population").right());
+        cols.push(Column::typed(|c: &City| c.population, NumberFormat::default()).header("Population").footer_sum());
     }
 
 
-    let mut stream = Stream::new(&mut handle, cols);
+    let mut stream = Stream::new(stdout().lock(), cols).detect_terminal();
     if let Some(g) = opts.grow {
         stream = stream.grow(g);
     }
     if let Some(title) = opts.title {
         stream = stream.title(&title);
     }
-    stream = stream.borders(opts.borders).padding(!opts.no_padding);
+    stream = stream.borders(opts.borders).padding(!opts.no_padding).wrap(opts.wrap).format(opts.format);
+    if opts.total {
+        stream = stream.footer_row();
+    }
 
     let cities = if opts.unicode { cities_unicode() } else { largest_cities() };
-    let total_pop: u32 = cities.iter().map(|c| c.population).sum();
 
     // Generally don't want to clone like this but just doing so to simulate long tables:
     for city in cities.iter().cycle().take(opts.repeat as usize * cities.len()).cloned() {
         stream.row(city)?;
     }
 
-    if opts.total {
-        let footer = format!("Total Population: {}", total_pop);
-        stream.footer(&footer)?;
-    } else {
-        stream.finish()?;
-    }
+    stream.finish()?;
 
     Ok(())
 }
@@ -67,6 +62,14 @@ struct Opts {
     #[arg(long)]
     no_padding: bool,
 
+    /// Wrap long values instead of truncating them.
+    #[arg(long)]
+    wrap: bool,
+
+    /// Output format: table, csv, tsv, html, jsonl, latex, or rst.
+    #[arg(long, default_value = "table")]
+    format: Format,
+
     #[arg(long)]
     format_pop: bool,
 
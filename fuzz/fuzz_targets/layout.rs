@@ -0,0 +1,102 @@
+//! Fuzz table layout: no combination of columns, options, and cell contents should panic,
+//! and every line of a table should be the same width.
+//!
+//! Run with: `cargo +nightly fuzz run layout`
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use tablestream::{Column, Degrade, Direction, Stream};
+use unicode_width::UnicodeWidthStr;
+
+type Row = Vec<String>;
+
+#[derive(Arbitrary, Debug)]
+struct ColumnSpec {
+    header: Option<String>,
+    min_width: u8,
+    hidden: bool,
+    alignment: u8,
+    elide_prefix: bool,
+    degrade: u8,
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    columns: Vec<ColumnSpec>,
+    rows: Vec<Row>,
+    max_width: u8,
+    borders: bool,
+    padding: bool,
+    grow: bool,
+    rtl: bool,
+    title: Option<String>,
+    title_wrap: bool,
+    max_height: Option<u8>,
+    footer: Option<String>,
+}
+
+fn column(index: usize, spec: &ColumnSpec) -> Column<Row> {
+    let mut col = Column::new(move |f, row: &Row| {
+        f.write_str(row.get(index).map(String::as_str).unwrap_or(""))
+    }).min_width(spec.min_width as usize);
+
+    if let Some(header) = &spec.header { col = col.header(header); }
+    if spec.hidden { col = col.hidden(); }
+    if spec.elide_prefix { col = col.elide_prefix(); }
+    col = match spec.degrade % 4 {
+        0 => col,
+        1 => col.degrade(&[Degrade::Wrap]),
+        2 => col.degrade(&[Degrade::Wrap, Degrade::Truncate, Degrade::Hide]),
+        _ => col.degrade(&[Degrade::Hide]),
+    };
+    match spec.alignment % 3 {
+        0 => col.left(),
+        1 => col.right(),
+        _ => col.center(),
+    }
+}
+
+fn render(input: Input) -> std::io::Result<String> {
+    let columns = input.columns.iter().take(16).enumerate().map(|(i, spec)| column(i, spec)).collect();
+    let mut out = Vec::new();
+    let mut stream = Stream::new(&mut out, columns)
+        .max_width(input.max_width as usize)
+        .borders(input.borders)
+        .padding(input.padding)
+        .grow(input.grow)
+        .direction(if input.rtl { Direction::Rtl } else { Direction::Ltr })
+        .title_wrap(input.title_wrap)
+        .strict_layout(true);
+    if let Some(title) = &input.title {
+        stream = stream.title(title);
+    }
+    if let Some(height) = input.max_height {
+        stream = stream.max_height(height as usize);
+    }
+
+    for row in input.rows {
+        stream.row(row)?;
+    }
+    match &input.footer {
+        Some(footer) => stream.footer(footer)?,
+        None => stream.finish()?,
+    };
+
+    Ok(String::from_utf8(out).expect("valid UTF-8"))
+}
+
+fuzz_target!(|input: Input| {
+    let out = render(input).expect("strict layout");
+
+    // Notes may follow the table's last rule, and aren't part of the table:
+    let lines: Vec<_> = out.lines().collect();
+    let table_end = lines.iter().rposition(|l| !l.is_empty() && l.chars().all(|c| c == '-'));
+    let table = &lines[..table_end.map(|i| i + 1).unwrap_or(0)];
+
+    let mut widths = table.iter().map(|l| l.width());
+    if let Some(first) = widths.next() {
+        assert!(widths.all(|w| w == first), "uneven lines:\n{}", out);
+    }
+});
@@ -0,0 +1,62 @@
+//! Writing delimited records: CSV (RFC 4180, but with `\n` line endings), and TSV.
+
+use std::io::{self, Write};
+
+/// Write one CSV record, quoting fields only where necessary.
+pub(crate) fn write_record<'a, W: Write + ?Sized>(
+    out: &mut W,
+    fields: impl IntoIterator<Item = &'a str>,
+) -> io::Result<()> {
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        write_field(out, field)?;
+    }
+    out.write_all(b"\n")
+}
+
+fn write_field<W: Write + ?Sized>(out: &mut W, field: &str) -> io::Result<()> {
+    let needs_quotes = field.contains([',', '"', '\r', '\n']);
+    if !needs_quotes {
+        return out.write_all(field.as_bytes());
+    }
+
+    out.write_all(b"\"")?;
+    for (i, part) in field.split('"').enumerate() {
+        if i > 0 {
+            out.write_all(b"\"\"")?;
+        }
+        out.write_all(part.as_bytes())?;
+    }
+    out.write_all(b"\"")
+}
+
+/// Write one TSV record.
+///
+/// TSV can't quote fields, so tabs, newlines, and backslashes are escaped as
+/// `\t`, `\n`, `\r`, and `\\`. (Like PostgreSQL's `COPY` format.)
+pub(crate) fn write_tsv_record<'a, W: Write + ?Sized>(
+    out: &mut W,
+    fields: impl IntoIterator<Item = &'a str>,
+) -> io::Result<()> {
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            out.write_all(b"\t")?;
+        }
+        let mut rest = field;
+        while let Some(pos) = rest.find(['\t', '\n', '\r', '\\']) {
+            out.write_all(&rest.as_bytes()[..pos])?;
+            let escaped: &[u8] = match rest.as_bytes()[pos] {
+                b'\t' => b"\\t",
+                b'\n' => b"\\n",
+                b'\r' => b"\\r",
+                _ => b"\\\\",
+            };
+            out.write_all(escaped)?;
+            rest = &rest[pos + 1..];
+        }
+        out.write_all(rest.as_bytes())?;
+    }
+    out.write_all(b"\n")
+}
@@ -0,0 +1,201 @@
+//! Writing rows in machine-readable formats. See: [`Stream::format`](crate::Stream::format).
+
+use std::io::{self, Write};
+
+use crate::{Alignment, Format, csv};
+
+/// One value to export, with its column's name and alignment, if set.
+pub(crate) struct Field<'a> {
+    pub(crate) text: &'a str,
+    pub(crate) key: String, // the column's name, unique among the fields.
+    pub(crate) alignment: Option<Alignment>,
+}
+
+/// Write everything that comes before the first row.
+pub(crate) fn write_start<W: Write>(out: &mut W, format: Format, rtl: bool, title: Option<&str>, headers: &[Field]) -> io::Result<()> {
+    match format {
+        Format::Html => {
+            let dir = if rtl { " dir=\"rtl\"" } else { "" };
+            writeln!(out, "<table{}>", dir)?;
+            if let Some(title) = title {
+                writeln!(out, "<caption>{}</caption>", Html(title))?;
+            }
+            writeln!(out, "<thead>")?;
+            write_html_row(out, "th", headers)?;
+            writeln!(out, "</thead>")?;
+            writeln!(out, "<tbody>")
+        },
+        Format::Latex => {
+            let spec: String = headers.iter().map(|h| match h.alignment {
+                None | Some(Alignment::Left) => 'l',
+                Some(Alignment::Center) => 'c',
+                Some(Alignment::Right) => 'r',
+            }).collect();
+            writeln!(out, "\\begin{{tabular}}{{{}}}", spec)?;
+            writeln!(out, "\\hline")?;
+            if let Some(title) = title {
+                writeln!(out, "\\multicolumn{{{}}}{{c}}{{{}}} \\\\", headers.len(), Latex(title))?;
+                writeln!(out, "\\hline")?;
+            }
+            write_latex_row(out, headers)?;
+            writeln!(out, "\\hline")
+        },
+        // Every object has its own keys:
+        Format::JsonLines => Ok(()),
+        _ => write_row(out, format, headers),
+    }
+}
+
+pub(crate) fn write_row<W: Write>(out: &mut W, format: Format, fields: &[Field]) -> io::Result<()> {
+    match format {
+        Format::Csv => csv::write_record(out, fields.iter().map(|f| f.text)),
+        Format::Tsv => csv::write_tsv_record(out, fields.iter().map(|f| f.text)),
+        Format::Html => write_html_row(out, "td", fields),
+        Format::JsonLines => write_json_object(out, fields),
+        Format::Latex => write_latex_row(out, fields),
+        Format::Table | Format::Rst => unreachable!("tables aren't exported"),
+    }
+}
+
+/// Write everything that comes after the last row.
+pub(crate) fn write_end<W: Write>(
+    out: &mut W,
+    format: Format,
+    num_cols: usize,
+    summary: Option<&[Field]>,
+    footer: Option<&str>,
+) -> io::Result<()> {
+    match format {
+        Format::Html => {
+            writeln!(out, "</tbody>")?;
+            if summary.is_some() || footer.is_some() {
+                writeln!(out, "<tfoot>")?;
+                if let Some(summary) = summary {
+                    write_html_row(out, "td", summary)?;
+                }
+                if let Some(footer) = footer {
+                    writeln!(out, "<tr><td colspan=\"{}\">{}</td></tr>", num_cols, Html(footer))?;
+                }
+                writeln!(out, "</tfoot>")?;
+            }
+            writeln!(out, "</table>")
+        },
+        Format::Latex => {
+            writeln!(out, "\\hline")?;
+            if let Some(summary) = summary {
+                write_latex_row(out, summary)?;
+                writeln!(out, "\\hline")?;
+            }
+            if let Some(footer) = footer {
+                writeln!(out, "\\multicolumn{{{}}}{{c}}{{{}}} \\\\", num_cols, Latex(footer))?;
+                writeln!(out, "\\hline")?;
+            }
+            writeln!(out, "\\end{{tabular}}")
+        },
+        _ => match summary {
+            Some(summary) => write_row(out, format, summary),
+            None => Ok(()),
+        },
+    }
+}
+
+fn write_html_row<W: Write>(out: &mut W, tag: &str, fields: &[Field]) -> io::Result<()> {
+    write!(out, "<tr>")?;
+    for field in fields {
+        let style = match field.alignment {
+            None => "",
+            Some(Alignment::Left) => " style=\"text-align:left\"",
+            Some(Alignment::Center) => " style=\"text-align:center\"",
+            Some(Alignment::Right) => " style=\"text-align:right\"",
+        };
+        write!(out, "<{0}{1}>{2}</{0}>", tag, style, Html(field.text))?;
+    }
+    writeln!(out, "</tr>")
+}
+
+fn write_json_object<W: Write>(out: &mut W, fields: &[Field]) -> io::Result<()> {
+    write!(out, "{{")?;
+    for (pos, field) in fields.iter().enumerate() {
+        if pos > 0 {
+            write!(out, ",")?;
+        }
+        write!(out, "{}:{}", Json(&field.key), Json(field.text))?;
+    }
+    writeln!(out, "}}")
+}
+
+fn write_latex_row<W: Write>(out: &mut W, fields: &[Field]) -> io::Result<()> {
+    for (pos, field) in fields.iter().enumerate() {
+        if pos > 0 {
+            write!(out, " & ")?;
+        }
+        write!(out, "{}", Latex(field.text))?;
+    }
+    writeln!(out, " \\\\")
+}
+
+/// Displays text escaped for LaTeX.
+struct Latex<'a>(&'a str);
+
+impl std::fmt::Display for Latex<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use std::fmt::Write;
+
+        for c in self.0.chars() {
+            match c {
+                '&' | '%' | '$' | '#' | '_' | '{' | '}' => write!(f, "\\{}", c)?,
+                '~' => f.write_str("\\textasciitilde{}")?,
+                '^' => f.write_str("\\textasciicircum{}")?,
+                '\\' => f.write_str("\\textbackslash{}")?,
+                // A newline would end the row:
+                '\n' | '\r' => f.write_char(' ')?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Displays text as a quoted JSON string.
+struct Json<'a>(&'a str);
+
+impl std::fmt::Display for Json<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use std::fmt::Write;
+
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+/// Displays text escaped for HTML.
+struct Html<'a>(&'a str);
+
+impl std::fmt::Display for Html<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut rest = self.0;
+        while let Some(pos) = rest.find(['&', '<', '>', '"', '\'']) {
+            f.write_str(&rest[..pos])?;
+            f.write_str(match &rest[pos..pos + 1] {
+                "&" => "&amp;",
+                "<" => "&lt;",
+                ">" => "&gt;",
+                "\"" => "&quot;",
+                _ => "&#39;",
+            })?;
+            rest = &rest[pos + 1..];
+        }
+        f.write_str(rest)
+    }
+}
@@ -0,0 +1,68 @@
+//! Per-column cells in a footer row. See: [`Stream::footer_row`](crate::Stream::footer_row).
+
+use std::fmt;
+
+use crate::NumberWriter;
+
+/// What to display in a column's footer cell.
+pub(crate) enum FooterCell {
+    Text(String),
+    Count(usize),
+    Sum(Aggregate),
+    Avg(Aggregate),
+}
+
+impl FooterCell {
+    /// Does this cell need to see each row's value?
+    pub(crate) fn aggregates(&self) -> bool {
+        !matches!(self, FooterCell::Text(_))
+    }
+
+    /// Add a row's value to the aggregate. Values that aren't numbers (`None`) are skipped.
+    pub(crate) fn add(&mut self, value: Option<f64>) {
+        match self {
+            FooterCell::Text(_) => {},
+            FooterCell::Count(count) => *count += 1,
+            FooterCell::Sum(aggregate) | FooterCell::Avg(aggregate) => {
+                if let Some(value) = value {
+                    aggregate.total += value;
+                    aggregate.count += 1;
+                }
+            },
+        }
+    }
+
+    /// The text of this cell. Totals are written with `write_number`, if the column has one.
+    pub(crate) fn text(&self, write_number: Option<&NumberWriter>) -> String {
+        let number = match self {
+            FooterCell::Text(text) => return text.clone(),
+            FooterCell::Count(1) => return "1 row".into(),
+            FooterCell::Count(count) => return format!("{} rows", count),
+            FooterCell::Sum(sum) => sum.total,
+            FooterCell::Avg(avg) if avg.count == 0 => return String::new(),
+            FooterCell::Avg(avg) => avg.total / avg.count as f64,
+        };
+        match write_number {
+            Some(writer) => Total { number, writer }.to_string(),
+            None => String::new(),
+        }
+    }
+}
+
+/// A running total of numeric values.
+#[derive(Default)]
+pub(crate) struct Aggregate {
+    total: f64,
+    count: usize,
+}
+
+struct Total<'a> {
+    number: f64,
+    writer: &'a NumberWriter,
+}
+
+impl fmt::Display for Total<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.writer)(f, self.number)
+    }
+}
@@ -0,0 +1,658 @@
+//! Formatting typed values for display in a column. See: [`Column::typed`](crate::Column::typed).
+
+use std::{
+    convert::TryInto,
+    fmt::{self, Display, Write},
+    time::Duration,
+};
+
+use crate::width::str_width;
+
+/// Knows how to display values of type `V` in a column.
+///
+/// This is implemented for closures like `|f: &mut fmt::Formatter, value: &V| write!(f, "{}", value)`,
+/// as well as helpers like [`NumberFormat`].
+pub trait CellFormat<V> {
+    /// Write the value. (Don't pad or align it. The Stream will do that for you.)
+    fn write(&self, f: &mut fmt::Formatter, value: &V) -> fmt::Result;
+
+    /// Does this format display numbers? If so, columns will be right-aligned by default.
+    fn is_numeric(&self) -> bool { false }
+
+    /// The value as a number, for totals in a footer row. (See: [`Column::footer_sum`](crate::Column::footer_sum))
+    /// `None` if the value isn't a number, so can't be totalled.
+    fn number(&self, _value: &V) -> Option<f64> { None }
+
+    /// Write a total or average of [`number`](CellFormat::number)s, like values are written.
+    fn write_number(&self, f: &mut fmt::Formatter, number: f64) -> fmt::Result {
+        write!(f, "{}", number)
+    }
+}
+
+impl <V, F> CellFormat<V> for F
+where F: Fn(&mut fmt::Formatter, &V) -> fmt::Result
+{
+    fn write(&self, f: &mut fmt::Formatter, value: &V) -> fmt::Result {
+        self(f, value)
+    }
+}
+
+/// Displays numbers.
+#[derive(Debug, Clone, Default)]
+pub struct NumberFormat {
+    precision: Option<usize>,
+    thousands: Option<char>,
+    digits: Digits,
+}
+
+impl NumberFormat {
+    /// Show exactly this many digits after the decimal point. (Ignored for integers.)
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Separate groups of thousands with `separator`. (ex: `24,150,000` with `','`)
+    /// See also: [`group_thousands`]
+    pub fn thousands(mut self, separator: char) -> Self {
+        self.thousands = Some(separator);
+        self
+    }
+
+    /// Display numbers with these digits. (default: [`Digits::Ascii`])
+    pub fn digits(mut self, digits: Digits) -> Self {
+        self.digits = digits;
+        self
+    }
+
+    /// Write a formatted `number` with our separators and digits.
+    fn write_digits(&self, f: &mut fmt::Formatter, mut number: String) -> fmt::Result {
+        if let Some(separator) = self.thousands {
+            number = group_thousands(&number, separator);
+        }
+        if self.digits == Digits::Ascii {
+            return f.write_str(&number);
+        }
+        number.chars().map(|c| self.digits.shape(c)).try_for_each(|c| f.write_char(c))
+    }
+}
+
+impl <V: Display> CellFormat<V> for NumberFormat {
+    fn write(&self, f: &mut fmt::Formatter, value: &V) -> fmt::Result {
+        let number = match self.precision {
+            Some(precision) => format!("{:.*}", precision, value),
+            None => value.to_string(),
+        };
+        self.write_digits(f, number)
+    }
+
+    fn is_numeric(&self) -> bool { true }
+
+    fn number(&self, value: &V) -> Option<f64> {
+        // A number's own Display is exact, unlike what we write. (ex: rounded, or grouped)
+        value.to_string().parse().ok()
+    }
+
+    /// Totals have our precision, or up to 6 digits after the decimal point without one.
+    fn write_number(&self, f: &mut fmt::Formatter, number: f64) -> fmt::Result {
+        let number = match self.precision {
+            Some(precision) => format!("{:.*}", precision, number),
+            None => {
+                let number = format!("{:.6}", number);
+                number.trim_end_matches('0').trim_end_matches('.').to_string()
+            },
+        };
+        self.write_digits(f, number)
+    }
+}
+
+/// Separate groups of thousands in a formatted `number` with `separator`, for use in your
+/// own column closures. Only the digits before any decimal point are grouped.
+///
+/// ```
+/// # use tablestream::*;
+/// assert_eq!("24,150,000", group_thousands("24150000", ','));
+/// assert_eq!("-1 234.5678", group_thousands("-1234.5678", ' '));
+///
+/// struct City { population: u32 }
+/// let col = Column::new(|f, c: &City| f.write_str(&group_thousands(&c.population.to_string(), ',')));
+/// ```
+pub fn group_thousands(number: &str, separator: char) -> String {
+    let start = number.find(|c: char| c.is_ascii_digit()).unwrap_or(number.len());
+    let len = number[start..].find(|c: char| !c.is_ascii_digit()).unwrap_or(number.len() - start);
+    let (head, rest) = number.split_at(start);
+    let (digits, tail) = rest.split_at(len);
+
+    let mut grouped = String::with_capacity(number.len() + len / 3 * separator.len_utf8());
+    grouped.push_str(head);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (len - i) % 3 == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped.push_str(tail);
+    grouped
+}
+
+/// The digits used to display numbers. See: [`NumberFormat::digits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Digits {
+    /// `0123456789`
+    #[default]
+    Ascii,
+    /// Arabic-Indic digits, used with Arabic: `٠١٢٣٤٥٦٧٨٩`, with `٫` as the decimal separator.
+    ArabicIndic,
+    /// Extended Arabic-Indic digits, used with Persian and Urdu: `۰۱۲۳۴۵۶۷۸۹`,
+    /// with `٫` as the decimal separator.
+    Persian,
+    /// Devanagari digits, used with Hindi and others: `०१२३४५६७८९`
+    Devanagari,
+    /// Bengali digits: `০১২৩৪৫৬৭৮৯`
+    Bengali,
+}
+
+impl Digits {
+    /// The digit zero. (The rest follow it, in order.)
+    fn zero(self) -> char {
+        match self {
+            Digits::Ascii => '0',
+            Digits::ArabicIndic => '\u{0660}',
+            Digits::Persian => '\u{06F0}',
+            Digits::Devanagari => '\u{0966}',
+            Digits::Bengali => '\u{09E6}',
+        }
+    }
+
+    /// Convert an ASCII digit or decimal point to this set of digits.
+    fn shape(self, c: char) -> char {
+        match c {
+            '0'..='9' => {
+                let offset = c as u32 - '0' as u32;
+                char::from_u32(self.zero() as u32 + offset).unwrap_or(c)
+            },
+            '.' if matches!(self, Digits::ArabicIndic | Digits::Persian) => '\u{066B}',
+            _ => c,
+        }
+    }
+}
+
+/// Displays a number of bytes in human-readable units. (ex: `1.2 MiB`)
+/// Negative numbers, like changes in size, are shown with a sign. (ex: `-2.0 KiB`)
+///
+/// ```
+/// # use tablestream::*;
+/// struct File { size: u64 }
+/// let col = Column::typed(|f: &File| f.size, ByteFormat::default());
+/// ```
+///
+/// See also: [`format_bytes`], to format sizes in your own column closures.
+#[derive(Debug, Clone)]
+pub struct ByteFormat {
+    decimal: bool,
+    precision: usize,
+}
+
+impl Default for ByteFormat {
+    fn default() -> Self {
+        Self { decimal: false, precision: 1 }
+    }
+}
+
+impl ByteFormat {
+    /// Use powers of 1000 (`kB`, `MB`, ...) instead of 1024 (`KiB`, `MiB`, ...),
+    /// ex: for disk or network sizes.
+    pub fn decimal(mut self) -> Self {
+        self.decimal = true;
+        self
+    }
+
+    /// Show this many digits after the decimal point. (default: 1)
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    fn format(&self, bytes: u64) -> String {
+        let (base, units) = match self.decimal {
+            true => (1000.0, ["B", "kB", "MB", "GB", "TB", "PB", "EB"]),
+            false => (1024.0, ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"]),
+        };
+        let mut value = bytes as f64;
+        let mut unit = 0;
+        // Move up a unit for values that would round up to `base`. (ex: `1024.0 KiB`)
+        while round(value, self.precision) >= base && unit < units.len() - 1 {
+            value /= base;
+            unit += 1;
+        }
+        match unit {
+            0 => format!("{} B", bytes),
+            _ => format!("{:.*} {}", self.precision, value, units[unit]),
+        }
+    }
+}
+
+impl ByteFormat {
+    fn write_signed(&self, f: &mut fmt::Formatter, bytes: i128) -> fmt::Result {
+        // Negative sizes (ex: a change in size) are shown with a sign:
+        if bytes < 0 {
+            f.write_char('-')?;
+        }
+        let bytes = bytes.unsigned_abs().try_into().unwrap_or(u64::MAX);
+        f.write_str(&self.format(bytes))
+    }
+}
+
+impl <V: Copy + TryInto<i128>> CellFormat<V> for ByteFormat {
+    fn write(&self, f: &mut fmt::Formatter, value: &V) -> fmt::Result {
+        self.write_signed(f, (*value).try_into().unwrap_or(i128::MAX))
+    }
+
+    fn is_numeric(&self) -> bool { true }
+
+    fn number(&self, value: &V) -> Option<f64> {
+        let bytes: i128 = (*value).try_into().ok()?;
+        Some(bytes as f64)
+    }
+
+    fn write_number(&self, f: &mut fmt::Formatter, number: f64) -> fmt::Result {
+        self.write_signed(f, number.round() as i128)
+    }
+}
+
+/// `value`, rounded to `precision` digits after the decimal point.
+fn round(value: f64, precision: usize) -> f64 {
+    let scale = 10f64.powi(precision.min(i32::MAX as usize) as i32);
+    (value * scale).round() / scale
+}
+
+/// Format a number of bytes in human-readable binary units, with one decimal place.
+/// (ex: `1.2 MiB`) See: [`ByteFormat`] for other options.
+///
+/// ```
+/// # use tablestream::*;
+/// assert_eq!("1.2 MiB", format_bytes(1_234_567));
+/// assert_eq!("512 B", format_bytes(512));
+/// ```
+pub fn format_bytes(bytes: u64) -> String {
+    ByteFormat::default().format(bytes)
+}
+
+/// Displays a [`Duration`] in the units that suit it. (ex: `1h 23m 45s`, or `12.3ms`)
+///
+/// ```
+/// # use std::time::Duration;
+/// # use tablestream::*;
+/// struct Job { runtime: Duration }
+/// let col = Column::typed(|j: &Job| j.runtime, DurationFormat::default());
+/// ```
+///
+/// See also: [`format_duration`], to format durations in your own column closures.
+#[derive(Debug, Clone)]
+pub struct DurationFormat {
+    precision: usize,
+}
+
+impl Default for DurationFormat {
+    fn default() -> Self {
+        Self { precision: 1 }
+    }
+}
+
+impl DurationFormat {
+    /// Show this many digits after the decimal point, for durations under a minute.
+    /// (default: 1)
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    fn format(&self, duration: Duration) -> String {
+        const MINUTE: u64 = 60;
+        const HOUR: u64 = 60 * MINUTE;
+        const DAY: u64 = 24 * HOUR;
+
+        let nanos = duration.as_nanos();
+        let precision = self.precision;
+        // Values that would round up to the next unit are shown in it. (ex: not `1000.0ms`)
+        let shown = |unit: f64| round(nanos as f64 / unit, precision);
+        if nanos < 1_000 {
+            return format!("{}ns", nanos);
+        } else if shown(1e3) < 1e3 {
+            return format!("{:.*}µs", precision, nanos as f64 / 1e3);
+        } else if shown(1e6) < 1e3 {
+            return format!("{:.*}ms", precision, nanos as f64 / 1e6);
+        } else if shown(1e9) < MINUTE as f64 {
+            return format!("{:.*}s", precision, duration.as_secs_f64());
+        }
+
+        // Whole seconds from here on, but at least the minute we rounded up to:
+        let secs = duration.as_secs().max(MINUTE);
+        match secs {
+            ..HOUR => format!("{}m {}s", secs / MINUTE, secs % MINUTE),
+            HOUR..DAY => format!("{}h {}m {}s", secs / HOUR, secs % HOUR / MINUTE, secs % MINUTE),
+            _ => format!("{}d {}h {}m", secs / DAY, secs % DAY / HOUR, secs % HOUR / MINUTE),
+        }
+    }
+}
+
+impl CellFormat<Duration> for DurationFormat {
+    fn write(&self, f: &mut fmt::Formatter, value: &Duration) -> fmt::Result {
+        f.write_str(&self.format(*value))
+    }
+
+    fn is_numeric(&self) -> bool { true }
+
+    fn number(&self, value: &Duration) -> Option<f64> {
+        Some(value.as_secs_f64())
+    }
+
+    fn write_number(&self, f: &mut fmt::Formatter, secs: f64) -> fmt::Result {
+        f.write_str(&self.format(Duration::try_from_secs_f64(secs).unwrap_or_default()))
+    }
+}
+
+/// Format a [`Duration`] in the units that suit it, with one decimal place for durations under
+/// a minute. (ex: `1h 23m 45s`, or `12.3ms`) See: [`DurationFormat`] for other options.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use tablestream::*;
+/// assert_eq!("1h 23m 45s", format_duration(Duration::from_secs(5025)));
+/// assert_eq!("12.3ms", format_duration(Duration::from_micros(12_345)));
+/// ```
+pub fn format_duration(duration: Duration) -> String {
+    DurationFormat::default().format(duration)
+}
+
+/// Displays dates and times from the [`chrono`] crate, with a `strftime`-style pattern.
+/// (Requires the `chrono` feature.)
+///
+/// ```
+/// # use tablestream::*;
+/// struct Event { at: chrono::NaiveDateTime }
+/// let col = Column::typed(|e: &Event| e.at, DateTimeFormat::new("%Y-%m-%d %H:%M"));
+/// ```
+///
+/// See: [`chrono::format::strftime`] for the pattern syntax. Patterns that don't suit the
+/// value (ex: `%H` for a `NaiveDate`) cause an error when the row is written.
+///
+/// See also: [`Column::datetime`](crate::Column::datetime), which keeps the column at a
+/// constant width.
+#[cfg(feature = "chrono")]
+#[derive(Debug, Clone)]
+pub struct DateTimeFormat {
+    pattern: String,
+    width: usize,
+}
+
+#[cfg(feature = "chrono")]
+impl DateTimeFormat {
+    /// Display values with `pattern`. (ex: `"%Y-%m-%d %H:%M:%S"`)
+    pub fn new(pattern: &str) -> Self {
+        let mut format = Self { pattern: pattern.to_string(), width: 0 };
+        format.width = format.measure();
+        format
+    }
+
+    /// The width of the widest value this pattern displays.
+    ///
+    /// Numbers are usually zero-padded, but names of months and weekdays (ex: `%B`), and
+    /// unpadded numbers (ex: `%-d`) vary in width.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Display a variety of dates and times, to find the widest.
+    fn measure(&self) -> usize {
+        use chrono::{FixedOffset, NaiveDate};
+
+        let utc = FixedOffset::east_opt(0).expect("valid offset");
+        let mut widest = 0;
+        let mut buf = String::new();
+        // Every month, and every weekday, in the morning and evening, with two-digit numbers:
+        for month in 1..=12 {
+            for day in 22..=28 {
+                for hour in [10, 22] {
+                    let time = NaiveDate::from_ymd_opt(2000, month, day)
+                        .and_then(|date| date.and_hms_nano_opt(hour, 59, 59, 123_456_789))
+                        .expect("valid date")
+                        .and_local_timezone(utc)
+                        .unwrap();
+                    buf.clear();
+                    // Invalid patterns will be reported when writing rows:
+                    if write!(buf, "{}", time.format(&self.pattern)).is_err() { return 0; }
+                    widest = widest.max(str_width(&buf));
+                }
+            }
+        }
+        widest
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl <Tz> CellFormat<chrono::DateTime<Tz>> for DateTimeFormat
+where
+    Tz: chrono::TimeZone,
+    Tz::Offset: Display,
+{
+    fn write(&self, f: &mut fmt::Formatter, value: &chrono::DateTime<Tz>) -> fmt::Result {
+        write!(f, "{}", value.format(&self.pattern))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl CellFormat<chrono::NaiveDateTime> for DateTimeFormat {
+    fn write(&self, f: &mut fmt::Formatter, value: &chrono::NaiveDateTime) -> fmt::Result {
+        write!(f, "{}", value.format(&self.pattern))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl CellFormat<chrono::NaiveDate> for DateTimeFormat {
+    fn write(&self, f: &mut fmt::Formatter, value: &chrono::NaiveDate) -> fmt::Result {
+        write!(f, "{}", value.format(&self.pattern))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl CellFormat<chrono::NaiveTime> for DateTimeFormat {
+    fn write(&self, f: &mut fmt::Formatter, value: &chrono::NaiveTime) -> fmt::Result {
+        write!(f, "{}", value.format(&self.pattern))
+    }
+}
+
+/// Displays a preview of binary data, as hexadecimal bytes. (ex: `de ad be ef …`)
+///
+/// ```
+/// # use tablestream::*;
+/// struct Packet { payload: Vec<u8> }
+/// let col = Column::hex(|p: &Packet| &p.payload, HexFormat::default().max_bytes(4));
+/// ```
+///
+/// See also: [`format_hex`], to format bytes in your own column closures.
+#[derive(Debug, Clone)]
+pub struct HexFormat {
+    max_bytes: usize,
+    group: usize,
+}
+
+impl Default for HexFormat {
+    fn default() -> Self {
+        Self { max_bytes: 8, group: 1 }
+    }
+}
+
+impl HexFormat {
+    /// Show at most this many bytes. If there are more, they're replaced with `…`. (default: 8)
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Put a space between each group of this many bytes. (ex: `2` for `dead beef`)
+    /// `0` doesn't put any spaces between bytes. (default: 1)
+    pub fn group(mut self, group: usize) -> Self {
+        self.group = group;
+        self
+    }
+
+    fn format(&self, bytes: &[u8]) -> String {
+        let shown = &bytes[..bytes.len().min(self.max_bytes)];
+        let mut hex = String::with_capacity(shown.len() * 3 + 4);
+        for (i, byte) in shown.iter().enumerate() {
+            if i > 0 && self.group > 0 && i % self.group == 0 {
+                hex.push(' ');
+            }
+            let _ = write!(hex, "{:02x}", byte);
+        }
+        if shown.len() < bytes.len() {
+            if !hex.is_empty() {
+                hex.push(' ');
+            }
+            hex.push('…');
+        }
+        hex
+    }
+}
+
+impl <V: AsRef<[u8]>> CellFormat<V> for HexFormat {
+    fn write(&self, f: &mut fmt::Formatter, value: &V) -> fmt::Result {
+        f.write_str(&self.format(value.as_ref()))
+    }
+}
+
+/// Format up to 8 bytes as hexadecimal, separated by spaces. (ex: `de ad be ef`)
+/// See: [`HexFormat`] for other options.
+///
+/// ```
+/// # use tablestream::*;
+/// assert_eq!("de ad be ef", format_hex(&[0xde, 0xad, 0xbe, 0xef]));
+/// assert_eq!("30 31 32 33 34 35 36 37 …", format_hex(b"0123456789"));
+/// ```
+pub fn format_hex(bytes: &[u8]) -> String {
+    HexFormat::default().format(bytes)
+}
+
+/// How to display `true` and `false`. See: [`Column::boolean`](crate::Column::boolean).
+///
+/// This is also a [`CellFormat`] for `bool` values:
+///
+/// ```
+/// # use tablestream::*;
+/// struct Feature { enabled: bool }
+/// let col = Column::typed(|f: &Feature| f.enabled, BoolStyle::YesNo);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BoolStyle {
+    /// `✓` and `✗`.
+    CheckCross,
+    /// `[x]` and `[ ]`. An ASCII alternative to `CheckCross`, for terminals without Unicode.
+    Checkbox,
+    /// `yes` and `no`.
+    YesNo,
+    /// `true` and `false`.
+    TrueFalse,
+}
+
+impl BoolStyle {
+    /// The text to display for `value`.
+    pub fn text(self, value: bool) -> &'static str {
+        match (self, value) {
+            (BoolStyle::CheckCross, true) => "✓",
+            (BoolStyle::CheckCross, false) => "✗",
+            (BoolStyle::Checkbox, true) => "[x]",
+            (BoolStyle::Checkbox, false) => "[ ]",
+            (BoolStyle::YesNo, true) => "yes",
+            (BoolStyle::YesNo, false) => "no",
+            (BoolStyle::TrueFalse, true) => "true",
+            (BoolStyle::TrueFalse, false) => "false",
+        }
+    }
+}
+
+impl CellFormat<bool> for BoolStyle {
+    fn write(&self, f: &mut fmt::Formatter, value: &bool) -> fmt::Result {
+        f.write_str(self.text(*value))
+    }
+}
+
+/// The outcome of some check or job, for display in a status column. See: [`StatusFormat`].
+///
+/// Implement `From<YourEnum> for Status` to display your own type with a [`StatusFormat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Everything's fine.
+    Ok,
+    /// Something needs attention.
+    Warn,
+    /// Something went wrong.
+    Fail,
+}
+
+/// Displays a [`Status`] (or anything that converts into one) as a glyph or short word.
+///
+/// ```
+/// # use tablestream::*;
+/// enum Health { Up, Degraded, Down }
+/// impl From<&Health> for Status {
+///     fn from(health: &Health) -> Status {
+///         match health {
+///             Health::Up => Status::Ok,
+///             Health::Degraded => Status::Warn,
+///             Health::Down => Status::Fail,
+///         }
+///     }
+/// }
+/// struct Service { health: Health }
+/// let col = Column::typed(|s: &Service| Status::from(&s.health), StatusFormat::emoji());
+/// ```
+///
+/// Terminals disagree about how wide some emoji are, which misaligns tables. So, glyphs are
+/// normalized to take exactly 2 cells: Variation selectors (which ask for an emoji-style
+/// glyph, ex: `⚠️`) are removed, and narrow glyphs are followed by a space.
+#[derive(Debug, Clone)]
+pub struct StatusFormat {
+    ok: String,
+    warn: String,
+    fail: String,
+}
+
+impl StatusFormat {
+    /// `✅`, `⚠`, and `❌`.
+    pub fn emoji() -> Self {
+        Self::glyphs("✅", "⚠️", "❌")
+    }
+
+    /// `ok`, `warn`, and `fail`. (ex: for terminals without emoji, or for logs)
+    pub fn text() -> Self {
+        Self { ok: "ok".into(), warn: "warn".into(), fail: "fail".into() }
+    }
+
+    /// Your own glyphs for each status. They'll be normalized to 2 cells wide.
+    pub fn glyphs(ok: &str, warn: &str, fail: &str) -> Self {
+        Self { ok: normalize(ok), warn: normalize(warn), fail: normalize(fail) }
+    }
+}
+
+impl <V: Clone + Into<Status>> CellFormat<V> for StatusFormat {
+    fn write(&self, f: &mut fmt::Formatter, value: &V) -> fmt::Result {
+        let glyph = match value.clone().into() {
+            Status::Ok => &self.ok,
+            Status::Warn => &self.warn,
+            Status::Fail => &self.fail,
+        };
+        f.write_str(glyph)
+    }
+}
+
+/// Make `glyph` exactly 2 cells wide, in terminals that agree with Unicode's widths.
+fn normalize(glyph: &str) -> String {
+    const VARIATION_SELECTORS: [char; 2] = ['\u{FE0E}', '\u{FE0F}'];
+    let mut glyph: String = glyph.chars().filter(|c| !VARIATION_SELECTORS.contains(c)).collect();
+    let width = str_width(&glyph);
+    glyph.extend(std::iter::repeat_n(' ', 2usize.saturating_sub(width)));
+    glyph
+}
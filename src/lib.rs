@@ -26,8 +26,7 @@
 //!    ]
 //! }
 //!
-//! let mut out = io::stdout();
-//! let mut stream = Stream::new(&mut out, vec![
+//! let mut stream = Stream::stdout(vec![
 //!     // There are three different ways to specify which data to show in each column.
 //!     // 1. A closure that takes a formatter, and a reference to your type, and writes it out.
 //!     Column::new(|f, c: &City| write!(f, "{}", &c.name)).header("City"),
@@ -52,39 +51,153 @@
 //! ```
 
 use std::{
-    cmp::max,
+    borrow::Cow,
+    cell::Cell,
+    cmp::{max, min},
+    collections::{HashMap, hash_map::Entry},
+    hash::Hash,
     fmt::{self, Write as FmtWrite},
-    io::{self, Write},
+    io::{self, IsTerminal, Write},
     marker::PhantomData,
-    mem
+    mem,
+    rc::Rc,
+    str::FromStr,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
 
-use unicode_truncate::UnicodeTruncateStr;
-use unicode_width::UnicodeWidthStr;
-
 #[cfg(test)]
 mod tests;
 
+/// How long [`Stream::receive`] waits for a row before flushing output.
+pub(crate) const IDLE_FLUSH: Duration = Duration::from_millis(500);
+mod csv;
+mod export;
+mod footer;
+mod format;
+mod lines;
+mod rollup;
+mod style;
+mod sync;
+pub mod testing;
+mod theme;
+mod width;
+
+pub use format::{
+    BoolStyle, ByteFormat, CellFormat, Digits, DurationFormat, HexFormat, NumberFormat, Status,
+    StatusFormat, format_bytes, format_duration, format_hex, group_thousands,
+};
+#[cfg(feature = "chrono")]
+pub use format::DateTimeFormat;
+pub use lines::{Fields, LineStream, Split};
+pub use style::{Color, ColorChoice, Style};
+pub use sync::{RowSender, SyncStream, TryRowError};
+pub use theme::{Frame, Theme};
+
+use export::Field;
+use footer::{Aggregate, FooterCell};
+use rollup::Rollup;
+use theme::{Decorations, Rule};
+use width::str_width;
+
 /// Allows printing rows of data to some io::Write.
 pub struct Stream<T, Out: Write> {
     // User options:
     columns: Vec<Column<T>>,
     max_width: usize,
+    requested_max_width: usize, // before adjusting for min widths.
+    min_table_width: usize, // See: match_width_of()
     grow: Option<bool>,
     output: Out,
-    borders: bool,
+    frame: Frame,
     padding: bool,
+    divider: Option<String>,
+    theme: Theme,
+    divided_rules: Option<bool>, // None: if the theme has junctions.
+    decorations: Decorations, // resolved from theme, frame, padding, and divider.
     title: Option<String>,
+    title_wrap: bool,
+    title_in_border: bool,
+    width_step: usize, // column widths are rounded up to a multiple of this.
+    stream_after: usize, // rows to buffer before calculating widths.
+    direction: Direction,
+    header_case: HeaderCase,
+    none_as: Option<String>, // placeholder for empty values, in columns without their own.
+    max_height: Option<usize>,
+    suppress_rows: bool,
+    quiet: bool,
+    strict_layout: bool,
+    notify: Option<(Duration, Notification)>, // when finished after at least this long.
+
+    // Rate limiting:
+    min_row_interval: Option<Duration>,
+    last_row_at: Option<Instant>,
+    fit_height: Option<usize>, // terminal height, if we should fit to the screen.
 
-    #[allow(dead_code)] // TODO
     wrap: bool,
+    format: Format,
+    key_values: bool, // See: Stream::key_values()
+    leader: char,
+    color: ColorChoice,
+    colors: bool, // resolved from `color`.
+    terminal: bool, // is output a terminal? See: Stream::detect_terminal()
+    fill_background: bool,
+    note_dropped: bool,
+    sparse: Option<(f64, Sparse)>, // the share of rows that must have values, and what to do if not.
+    order_by_width: bool,
+    order: Vec<usize>, // display position -> column index, if reordered. See: order_by_width()
+    truncation_marker: String, // empty: don't mark truncated values.
+    row_style: Option<Box<RowStyle<T>>>,
+    on_truncate: Option<Box<OnTruncate>>,
+    rollup: Option<Rollup<T>>,
+    row_age: Option<Box<RowAge<T>>>,
+    age_styles: Vec<(Duration, Style)>,
 
     sizes_calculated: bool,
+    sampled_rows: usize, // measured by sample_only(), but not displayed.
+    started: bool, // have we printed headers?
+    closed: bool, // did we print an interim footer, which must be reopened for more rows?
+    after_rule: bool, // was the last line we printed a horizontal rule?
+    after_dividers: bool, // did the last line we printed have column dividers?
     width: usize, // calculated.
-    buffer: Vec<T>,
+    buffer: Vec<Buffered<T>>,
+    summary_row: Option<T>,
+    footer_row: bool, // display columns' footer cells?
+    count_rows: bool, // end the table with a line that counts its rows?
+    pinned_top: Vec<T>,
+    pinned_bottom: Vec<T>,
+
+    // Collapsing of duplicate rows:
+    collapse: Option<Box<SameRow<T>>>,
+    chapter_key: Option<Box<ChapterKey<T>>>,
+    last_chapter: Option<String>, // of the last row received.
+    chapter: Option<String>, // being displayed.
+    pending: Option<(T, usize)>,
+    numbered: Option<Vec<T>>, // displayed rows, if numbered.
+    totals: Vec<RunningTotal<T>>, // for percent_of_total().
+
+    rows: usize, // total rows received.
+    first_row_at: Option<Instant>,
+    rows_printed: usize,
+    rows_displayed: usize, // including pinned rows.
+    rows_hidden: usize,
+    truncated_cells: usize,
+
+    // Notes to be printed below the table.
+    notes: Vec<String>,
+    legend: Vec<(String, String)>,
+    validation_errors: Vec<ValidationError>,
+    footnotes: bool,
+    footnote_count: usize,
 
     // It's handy to have a long-lived string buffer so we don't have to continue to reallocate.
     str_buf: String,
+    cells: Vec<String>,
+    highlights: Vec<Style>, // for each cell, from Column::highlight.
+
+    view: Option<Box<dyn Write>>, // a CSV record of what was displayed.
+    view_started: bool, // have we written the view's header record?
 
     _pd: PhantomData<T>,
 }
@@ -92,31 +205,106 @@ pub struct Stream<T, Out: Write> {
 
 impl <T, Out: Write> Stream<T, Out> {
     /// Create a new table streamer.
-    pub fn new(output: Out, columns: Vec<Column<T>>) -> Self {
-        let mut term_width = crossterm::terminal::size().map(|(w,_)| w as usize);
-        if cfg!(windows) {
-            // Windows Terminal has a weird bug. It seems to try to re-wrap text on resize. It does so if the
-            // text goes all the way to the edge of the terminal.  If we leave 1 colum extra, the behavior stops.
-            // 🤦‍♂️
-            // See: https://github.com/microsoft/terminal/issues/3088
-            term_width = term_width.map(|w| w - 1);
+    ///
+    /// The Stream can't tell whether an arbitrary `output` is a terminal, so it assumes it
+    /// isn't, and options for terminals (like colors) don't apply. To write to a terminal, use
+    /// [`Stream::stdout`], or [`Stream::detect_terminal`].
+    pub fn new(output: Out, mut columns: Vec<Column<T>>) -> Self {
+        let term_width = terminal_size().map(|(w, _)| w);
+        for col in columns.iter_mut() {
+            col.use_format(Format::Table);
         }
 
         Self{
             columns,
             max_width: 0,
+            requested_max_width: 0,
+            min_table_width: 0,
             width: 0, // calculated later.
             grow: None,
             output,
             wrap: false,
-            borders: false,
+            format: Format::Table,
+            key_values: false,
+            leader: '.',
+            color: ColorChoice::Auto,
+            colors: false,
+            terminal: false,
+            fill_background: true,
+            note_dropped: false,
+            sparse: None,
+            order_by_width: false,
+            order: vec![],
+            truncation_marker: String::new(),
+            row_style: None,
+            on_truncate: None,
+            rollup: None,
+            row_age: None,
+            age_styles: vec![],
+            frame: Frame::default(),
             padding: true,
+            divider: None,
+            theme: Theme::default(),
+            divided_rules: None,
+            decorations: Decorations::default(),
             title: None,
+            title_wrap: false,
+            title_in_border: false,
+            width_step: 1,
+            stream_after: 100,
+            direction: Direction::Ltr,
+            header_case: HeaderCase::AsIs,
+            none_as: None,
+            max_height: None,
+            suppress_rows: false,
+            quiet: false,
+            strict_layout: false,
+            notify: None,
+
+            min_row_interval: None,
+            last_row_at: None,
+            fit_height: None,
 
             sizes_calculated: false,
+            sampled_rows: 0,
+            started: false,
+            closed: false,
+            after_rule: false,
+            after_dividers: false,
             buffer: vec![],
+            summary_row: None,
+            footer_row: false,
+            count_rows: false,
+            pinned_top: vec![],
+            pinned_bottom: vec![],
+
+            collapse: None,
+            chapter_key: None,
+            last_chapter: None,
+            chapter: None,
+            pending: None,
+            numbered: None,
+            totals: vec![],
+
+            rows: 0,
+            first_row_at: None,
+            rows_printed: 0,
+            rows_displayed: 0,
+            rows_hidden: 0,
+            truncated_cells: 0,
+
+            notes: vec![],
+            legend: vec![],
+            validation_errors: vec![],
+            footnotes: false,
+            footnote_count: 0,
 
             str_buf: String::new(),
+            cells: vec![],
+            highlights: vec![],
+
+            view: None,
+            view_started: false,
 
             _pd: Default::default(),
         }.max_width(
@@ -124,10 +312,45 @@ impl <T, Out: Write> Stream<T, Out> {
         )
     }
 
+    /// Create a list of keys and values, joined by dotted leaders.
+    ///
+    /// Like a table of contents, or a dump of settings:
+    /// ```text
+    /// Name .............. Cody
+    /// Favorite color .. yellow
+    /// ```
+    /// Keys are left-aligned, values are right-aligned. If they don't fit, keys are truncated
+    /// first. Values are never wrapped. See [`Stream::leader`] to change the leader character.
+    ///
+    /// If other columns are added (ex: by [`Stream::number_rows`]), the last column is the
+    /// value, and the others are joined into the key.
+    pub fn key_values(output: Out, key: Column<T>, value: Column<T>) -> Self {
+        let mut stream = Self::new(output, vec![key, value]).divider(" . ");
+        stream.key_values = true;
+        stream
+    }
+
+    /// Set the character used to join keys and values in a [`Stream::key_values`] list.
+    /// (default: `.`)
+    pub fn leader(mut self, leader: char) -> Self {
+        self.leader = leader;
+        self
+    }
+
     /// Enable right/left borders? (default: false)
+    ///
+    /// This is a shortcut for [`Frame::outer`]. See: [`Stream::frame`].
     pub fn borders(mut self, borders: bool) -> Self {
-        self.borders = borders;
-        let width = self.max_width;
+        self.frame.outer = borders;
+        let width = self.requested_max_width;
+        self.max_width(width)
+    }
+
+    /// Choose which parts of the table's frame to draw:
+    /// borders, dividers between columns, and the rule below headers.
+    pub fn frame(mut self, frame: Frame) -> Self {
+        self.frame = frame;
+        let width = self.requested_max_width;
         self.max_width(width)
     }
 
@@ -136,30 +359,73 @@ impl <T, Out: Write> Stream<T, Out> {
     /// specified columns, borders, dividers, and paddings with sizes
     /// that require a larger max_width.
     pub fn max_width(mut self, max_width: usize) -> Self {
-        let num_cols = self.columns.len();
-        let padding = if self.padding { 1 } else { 0 };
-        let dividers = (num_cols - 1) * (1 + 2*padding);
-        let border = if self.borders { 1 } else { 0 };
-        let borders = border * (border + padding) * 2;
+        self.set_max_width(max_width);
+        self
+    }
 
-        let col_widths = self.columns.iter().map(|c| c.min_width).sum::<usize>();
-        let min_width = col_widths + borders + dividers;
+    fn set_max_width(&mut self, max_width: usize) {
+        // Anything that affects the max width may also affect decorations, so update them here:
+        self.decorations = Decorations::new(&self.theme, self.frame, self.padding, self.divider.as_deref());
+
+        self.requested_max_width = max_width;
+        // Columns that can be hidden don't need to fit:
+        let required = || self.visible_columns().filter(|c| !c.can_hide());
+        let col_widths = required().map(|c| c.min_width).sum::<usize>();
+        let min_width = col_widths + self.dividers_for(required().count()) + self.borders_width();
         self.max_width = max(max_width, min_width);
+        self.max_width = max(self.max_width, self.min_table_width);
 
         // If the user sets a long title, that likewise bumps up our max-width.
-        let title_width = self.title.as_ref().map(|t| t.width()).unwrap_or(0) + borders;
+        // (Unless we can wrap it.)
+        if self.title_wrap { return; }
+        let title_width = self.title.as_deref().map(str_width).unwrap_or(0) + self.borders_width();
         self.max_width = max(self.max_width, title_width);
-        
-        self
+    }
+
+    /// Make the table at least as wide as the longest line of `content`, so that it lines up
+    /// with other output. (ex: a banner printed above the table)
+    ///
+    /// Width is measured in terminal columns, ignoring any ANSI color codes.
+    /// This also bumps up the max_width, if necessary.
+    pub fn match_width_of(mut self, content: &str) -> Self {
+        self.min_table_width = content.lines().map(str_width).max().unwrap_or(0);
+        let width = self.requested_max_width;
+        self.max_width(width)
     }
 
     /// Enable horizontal padding around `|` dividers and inside external borders. (default: true)
     pub fn padding(mut self, padding: bool) -> Self {
         self.padding = padding;
-        let width = self.max_width;
+        let width = self.requested_max_width;
+        self.max_width(width)
+    }
+
+    /// Set the string used to divide columns. (default: `" | "`, or `"|"` without padding)
+    ///
+    /// The divider should include any padding you want around it. (ex: `" :: "`)
+    /// This overrides the [`Theme`]'s divider.
+    pub fn divider(mut self, divider: &str) -> Self {
+        self.divider = Some(divider.to_string());
+        let width = self.requested_max_width;
+        self.max_width(width)
+    }
+
+    /// Set the characters used to draw the table. (default: [`Theme::default`])
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        let width = self.requested_max_width;
         self.max_width(width)
     }
 
+    /// Draw column dividers through horizontal rules? (ex: `----+-----+----`)
+    /// (default: only if the [`Theme`] has [`junctions`](Theme::junctions))
+    ///
+    /// Themes without junctions use `+` where rules meet dividers and borders.
+    pub fn divided_rules(mut self, divided: bool) -> Self {
+        self.divided_rules = Some(divided);
+        self
+    }
+
     /// Should the table grow to fit its max_size?
     /// 
     /// Default behavior is determined by how much data we send to Stream.
@@ -172,239 +438,1627 @@ impl <T, Out: Write> Stream<T, Out> {
         self
     }
 
-    /// Set a table title, to be displayed centered above the table.
-    pub fn title(mut self, title: &str) -> Self {
-        self.title = Some(title.to_string());
-        let width = self.max_width;
-        self.max_width(width)
+    /// Set the output format. (default: [`Format::Table`])
+    ///
+    /// This lets the same columns serve both humans and scripts. (ex: `--format csv`)
+    /// Machine-readable formats contain the full value of every column, including hidden
+    /// columns, and ignore options that only apply to terminal tables.
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        for col in self.columns.iter_mut() {
+            col.use_format(format);
+        }
+        if format == Format::Rst {
+            self.divider = None;
+            self = self.borders(true).padding(true).theme(Theme::rst());
+        }
+        self
     }
 
-    /// Print a single row.
-    /// Note: Stream may buffer some rows before it begins output to calculate 
-    /// column sizes.
-    pub fn row(&mut self, data: T) -> io::Result<()> {
+    /// Apply a group of settings for a particular kind of output. See: [`Profile`].
+    ///
+    /// Settings made after this one override the profile's.
+    ///
+    /// ```
+    /// # use tablestream::*;
+    /// let report = Stream::new(Vec::new(), vec![
+    ///     Column::new(|f, job: &String| write!(f, "{}", job)).header("Job"),
+    /// ]).profile(Profile::Email).title("Nightly Report");
+    /// ```
+    pub fn profile(self, profile: Profile) -> Self {
+        let width = match profile {
+            Profile::Email => 72,
+            Profile::PlainText => 78,
+        };
+        self.theme(Theme::default())
+            .color(ColorChoice::Never)
+            .wrap(true)
+            .stream_after_rows(usize::MAX)
+            .max_width(width)
+    }
 
-        if self.sizes_calculated {
-            return self.print_row(data);
-        }
-        
-        self.buffer.push(data); 
-        if self.buffer.len() > 100 {
-            // Prefer to grow if unspecified, to allow extra space for rows to come:
-            self.grow = self.grow.or(Some(true));
-            self.write_buffer()?;
-        }
-        
-        Ok(())
+    /// Wrap long cell values onto multiple lines, instead of truncating them? (default: false)
+    ///
+    /// Wrapped values break at word boundaries where possible. Other cells in the row are
+    /// padded so that all columns stay aligned.
+    /// This only sets the default. See [`Column::degrade`] to choose for each column.
+    pub fn wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
     }
 
-    fn write_buffer(&mut self) -> io::Result<()> {
-        self.calc_sizes()?;
+    /// How many rows to buffer before calculating column widths and starting output.
+    /// (default: 100)
+    ///
+    /// Widths are calculated from buffered rows, so more rows give a better layout, at the
+    /// cost of waiting longer for output.
+    /// * `usize::MAX` buffers every row until [`Stream::finish`], for an exact layout.
+    /// * `0` starts output with the first row. Use [`Column::min_width`] to reserve space
+    ///   for values that are wider than the first row's.
+    ///
+    /// [`Stream::flush`] also ends buffering early.
+    pub fn stream_after_rows(mut self, rows: usize) -> Self {
+        self.stream_after = rows;
+        self
+    }
 
-        self.print_headers()?;
+    /// Don't buffer rows. Output starts as soon as the first row arrives.
+    ///
+    /// Best used when every column has a [`Column::fixed_width`], since any other columns
+    /// are sized to fit the first row. Same as `stream_after_rows(0)`.
+    pub fn no_buffer(self) -> Self {
+        self.stream_after_rows(0)
+    }
 
-        let buffer = mem::replace(&mut self.buffer, vec![]);
-        for row in buffer {
-            self.print_row(row)?;
-        }
+    /// Print a note below the table listing any columns that were hidden because the table
+    /// was too narrow for them. (default: false) See: [`Column::priority`].
+    pub fn note_dropped_columns(mut self, note: bool) -> Self {
+        self.note_dropped = note;
+        self
+    }
 
-        Ok(())
+    /// Hide columns that have values in fewer than `threshold` (ex: `0.1` for 10%) of the rows
+    /// buffered before output starts.
+    ///
+    /// Handy for tables of records with many optional fields, most of which are rarely set.
+    /// Hidden columns are listed in a note below the table if [`Stream::note_dropped_columns`]
+    /// is set. See also: [`Column::hide_if_empty`], and [`Stream::shrink_sparse_columns`].
+    pub fn hide_sparse_columns(mut self, threshold: f64) -> Self {
+        self.sparse = Some((threshold, Sparse::Hide));
+        self
     }
 
-    fn print_headers(&mut self) -> io::Result<()> {
-        self.hr()?;
+    /// Like [`Stream::hide_sparse_columns`], but keep sparse columns, only as wide as their
+    /// headers. Values that don't fit are marked with an ellipsis (`…`), or the
+    /// [`truncation_marker`](Stream::truncation_marker), if set.
+    pub fn shrink_sparse_columns(mut self, threshold: f64) -> Self {
+        self.sparse = Some((threshold, Sparse::Shrink));
+        self
+    }
 
-        let border_width = if self.borders { 1 } else { 0 } + if self.padding { 1 } else { 0 };
-        let title_width = self.width - (border_width * 2);
+    /// Mark truncated values with an ellipsis (`…`), so that it's clear that they were cut off.
+    /// (default: false)
+    ///
+    /// The marker goes where the value was cut. See: [`Column::truncate`].
+    pub fn mark_truncated(self, mark: bool) -> Self {
+        self.truncation_marker(if mark { "…" } else { "" })
+    }
 
-        if let Some(title) = &self.title {
-            let title = title.clone();
-            self.border_left()?;
-            Alignment::Center.write(&mut self.output, title_width, &title)?;
-            self.border_right()?;
-            self.hr()?;
-        }
+    /// Mark truncated values with `marker`, instead of an ellipsis. (ex: `"~"` for ASCII)
+    /// An empty marker disables marking. See: [`Stream::mark_truncated`].
+    pub fn truncation_marker(mut self, marker: &str) -> Self {
+        self.truncation_marker = width::printable(marker).into_owned();
+        self
+    }
 
-        let has_headers = self.columns.iter().any(|c| c.header.is_some());
-        if has_headers {
-            let divider = if self.padding { " | " } else { "|" };
-            self.border_left()?;
-            for (i, col) in self.columns.iter().enumerate() {
-                if i > 0 {
-                    write!(&mut self.output, "{}", divider)?;
-                }
-                let name = col.header.as_ref().map(|h| h.as_str()).unwrap_or("");
-                Alignment::Center.write(&mut self.output, col.width, name)?;
-            }
-            self.border_right()?;
-            self.hr()?;
-        }
+    /// Reorder columns by their measured width, from narrowest to widest. (default: false)
+    ///
+    /// The widest column is the one most likely to be truncated, and truncation is least
+    /// disruptive at the end of a line. Columns are sorted once, when widths are calculated.
+    /// Use [`Column::keep_position`] to keep some columns where you put them.
+    ///
+    /// Only the display order changes. Column indexes (ex: in [`Stream::on_truncate`] and
+    /// [`Summary::columns`]) still follow the order columns were given in.
+    pub fn order_by_width(mut self, order: bool) -> Self {
+        self.order_by_width = order;
+        self
+    }
 
-        Ok(())
+    /// Round column widths up to a multiple of `step`, if there's room. (default: 1)
+    ///
+    /// When the same command runs repeatedly (ex: with `watch`), small changes in data can
+    /// shift column widths between runs. Rounding makes the layout more stable.
+    pub fn quantize_widths(mut self, step: usize) -> Self {
+        self.width_step = max(step, 1);
+        self
+    }
+
+    /// Wrap long titles onto multiple lines? (default: false)
+    ///
+    /// By default, a title that is wider than `max_width` widens the table to fit it.
+    /// If enabled, the title is instead wrapped at word boundaries.
+    pub fn title_wrap(mut self, wrap: bool) -> Self {
+        self.title_wrap = wrap;
+        let width = self.requested_max_width;
+        self.max_width(width)
     }
 
-    fn hr(&mut self) -> io::Result<()> {
-        writeln!(&mut self.output, "{1:-<0$}", self.width, "")
+    /// Display the title inside the rule above the table, instead of on its own line?
+    /// (default: false)
+    ///
+    /// ex: `──┤ Cities ├──────────`. This saves two lines of vertical space.
+    /// Titles that don't fit are truncated.
+    pub fn title_in_border(mut self, in_border: bool) -> Self {
+        self.title_in_border = in_border;
+        self
     }
 
-    fn border_left(&mut self) -> io::Result<()> {
-        if self.borders {
-            let border = if self.padding { "| " } else { "|" };
-            write!(&mut self.output, "{}", border)?;
+    /// Set the direction of the table. (default: [`Direction::Ltr`])
+    ///
+    /// Right-to-left tables display their columns in reverse order, and align
+    /// cells to the right unless columns specify otherwise.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Transform all column headers, for a consistent style across tables.
+    /// (default: [`HeaderCase::AsIs`])
+    ///
+    /// This also applies to columns added later, and to the headers of exported formats.
+    pub fn header_case(mut self, case: HeaderCase) -> Self {
+        self.header_case = case;
+        for col in self.columns.iter_mut() {
+            col.apply_header_case(case);
         }
-        Ok(())
+        let width = self.requested_max_width;
+        self.max_width(width)
     }
-    fn border_right(&mut self) -> io::Result<()> {
-        if self.borders {
-            let border = if self.padding { " |" } else { "|" };
-            writeln!(&mut self.output, "{}", border)
-        } else {
-            writeln!(&mut self.output, "")
+
+    /// Display `placeholder` in place of empty values (ex: `None`) in columns that don't set
+    /// their own [`Column::none_as`]. (default: empty values are left empty)
+    ///
+    /// This also applies to columns added later.
+    pub fn none_as(mut self, placeholder: &str) -> Self {
+        let placeholder = width::printable(placeholder).into_owned();
+        for col in self.columns.iter_mut() {
+            col.none_as.get_or_insert_with(|| placeholder.clone());
         }
+        self.none_as = Some(placeholder);
+        self
+    }
+
+    /// Set a table title, to be displayed centered above the table.
+    ///
+    /// Titles and footers may contain variables, which are replaced when they are printed:
+    /// * `{rows}`: The number of rows received so far.
+    /// * `{truncated}`: The number of cells that have been truncated so far.
+    /// * `{elapsed}`: The time since the first row was received. (ex: `3.4s`)
+    /// * `{chapter}`: The current chapter. See: [`Stream::chapter_by`].
+    ///
+    /// Note that titles are printed before any rows, so only buffered rows will be counted.
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        let width = self.requested_max_width;
+        self.max_width(width)
     }
 
-    fn print_row(&mut self, row: T) -> io::Result<()> {
+    /// Limit the number of data rows displayed in the table.
+    ///
+    /// Once `max_height` rows have been printed, further rows are counted but not displayed,
+    /// and a `… (N more)` row is shown at the end of the table. Footers are still printed.
+    /// Hidden rows are still measured for [`Summary::untruncated_width`].
+    pub fn max_height(mut self, rows: usize) -> Self {
+        self.max_height = Some(rows);
+        self.fit_height = None;
+        self
+    }
 
-        let buf = &mut self.str_buf;
-        let out = &mut self.output;
+    /// Don't display data rows. (default: false)
+    ///
+    /// Rows are still counted, so this is useful to show only the headers and a summary row or
+    /// footer, using the same code that displays a full table.
+    pub fn suppress_rows(mut self, suppress: bool) -> Self {
+        self.suppress_rows = suppress;
+        self
+    }
 
-        if self.borders {
-            write!(out, "|")?;
-            if self.padding {
-                write!(out, " ")?;
-            }
-        }
+    /// Only print a one-line summary instead of the table. (default: false)
+    ///
+    /// Rows are counted, but otherwise ignored. [`Stream::finish`] prints the number of rows
+    /// (ex: `42 rows`), and [`Stream::footer`] prints only the footer.
+    /// This makes it easy to support a `--quiet` flag.
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
 
-        for (i, col) in self.columns.iter().enumerate() {
-            if i > 0 {
-                if self.padding {
-                    write!(out, " | ")?;
-                } else {
-                    write!(out, "|")?;
-                }
-            }
+    /// Return an error if the table layout is ever inconsistent. (default: false)
+    ///
+    /// Every line of a table should be exactly as wide as the table, and every cell exactly
+    /// as wide as its column. A violation is a bug in tablestream. Debug builds panic when
+    /// they find one, release builds ignore it. With `strict_layout`, it is instead returned
+    /// as an [`io::ErrorKind::InvalidData`] error, in any build.
+    pub fn strict_layout(mut self, strict: bool) -> Self {
+        self.strict_layout = strict;
+        self
+    }
 
-            buf.clear();
-            write!(
-                buf,
-                "{}", 
-                Displayer{ row: &row, writer: col.writer.as_ref() }
-            ).to_io()?;
+    /// Also write each displayed row to `out` as CSV, exactly as it was displayed.
+    ///
+    /// Values are recorded after formatting and truncation, including any markers (ex: `!`
+    /// for invalid values, or footnote references), but without padding. Wrapped values keep
+    /// their line breaks. This records "what the user actually saw", ex: for bug reports.
+    ///
+    /// The header record is written once, for the first table. Rows displayed after
+    /// [`Stream::add_column`] have a field for each new column.
+    pub fn record_view<W: Write + 'static>(mut self, out: W) -> Self {
+        self.view = Some(Box::new(out));
+        self
+    }
 
-            col.alignment.write(out, col.width, buf.as_str())?;
-        }
+    /// Get the user's attention when the table is finished, if it took at least `elapsed`.
+    ///
+    /// Handy for slow reports, which users tend to wander away from. The notification is
+    /// written to the output when [`Stream::finish`] or [`Stream::footer`] completes.
+    /// It's only written for [`Format::Table`], to avoid corrupting machine-readable output.
+    ///
+    /// Notifications are escape codes, so they're written only when colors would be, ex: when
+    /// output is a terminal. (See: [`Stream::color`])
+    pub fn notify_after(mut self, elapsed: Duration, notification: Notification) -> Self {
+        self.notify = Some((elapsed, notification));
+        self
+    }
 
-        if self.borders {
-            if self.padding {
-                write!(out, " ")?;
-            }
-            write!(out, "|")?;
-        }
+    /// Whether styles cover each cell's full width, or only its text. (default: true)
+    ///
+    /// Styling full cells makes background colors (ex: stripes or heatmaps) display as solid
+    /// blocks, instead of ragged highlights behind each value.
+    pub fn fill_background(mut self, fill: bool) -> Self {
+        self.fill_background = fill;
+        self
+    }
 
-        writeln!(out, "")?;
+    /// Choose whether to display colors and other styles. (default: [`ColorChoice::Auto`])
+    ///
+    /// `Auto` only displays them if output is a terminal. Streams created with
+    /// [`Stream::stdout`] check that for you. For other outputs, see: [`Stream::detect_terminal`].
+    pub fn color(mut self, choice: ColorChoice) -> Self {
+        self.color = choice;
+        self.colors = choice.enabled(self.terminal);
+        self
+    }
 
-        Ok(())
+    /// Choose a [`Style`] for each row, given its index among displayed rows, and its data.
+    ///
+    /// ```
+    /// # use std::io;
+    /// # use tablestream::*;
+    /// # struct Person { name: String }
+    /// // Zebra stripes:
+    /// let stream = Stream::stdout(vec![col!(Person: .name)])
+    ///     .row_style(|index, _: &Person| {
+    ///         if index % 2 == 1 { Style::default().bg(Color::DarkGrey) } else { Style::default() }
+    ///     });
+    /// ```
+    ///
+    /// Each cell is styled separately, so styles don't bleed into dividers or borders.
+    pub fn row_style<F>(mut self, style: F) -> Self
+    where F: Fn(usize, &T) -> Style + 'static
+    {
+        self.row_style = Some(Box::new(style));
+        self
     }
 
-    fn calc_sizes(&mut self) -> io::Result<()> {
-        if self.sizes_calculated { return Ok(()); }
-        self.sizes_calculated = true; // or will be very soon. :p
-
-
-        for row in &self.buffer {
-            for col in self.columns.iter_mut() {
-                self.str_buf.clear();
-                write!(
-                    &mut self.str_buf,
-                    "{}",
-                    Displayer{ row, writer: col.writer.as_ref() }
-                ).to_io()?;
-                let width = self.str_buf.width();
-                col.max_width = max(col.max_width, width);
-                col.width_sum += width;
-            }
-        }
+    /// Style rows by how old their data is, ex: to emphasize fresh data in `tail -f`-like tools.
+    ///
+    /// `age` returns how old a row is. Each row is displayed in the style of the first bucket
+    /// that it is younger than. Rows older than every bucket aren't styled.
+    ///
+    /// ```
+    /// # use std::{io, time::{Duration, SystemTime}};
+    /// # use tablestream::*;
+    /// struct LogLine { time: SystemTime, message: String }
+    ///
+    /// let stream = Stream::stdout(vec![col!(LogLine: .message)])
+    ///     .style_by_age(
+    ///         |line: &LogLine| line.time.elapsed().unwrap_or_default(),
+    ///         &[
+    ///             (Duration::from_secs(5), Style::default().bold()),
+    ///             (Duration::from_secs(60), Style::default()),
+    ///             (Duration::MAX, Style::default().dim()),
+    ///         ],
+    ///     );
+    /// ```
+    pub fn style_by_age<F>(mut self, age: F, buckets: &[(Duration, Style)]) -> Self
+    where F: Fn(&T) -> Duration + 'static
+    {
+        self.row_age = Some(Box::new(age));
+        self.age_styles = buckets.to_vec();
+        self
+    }
 
-        let num_cols = self.columns.len();
-        let padding = if self.padding { 1 } else { 0 };
-        let dividers = (num_cols - 1) * (1 + 2*padding);
-        let border = if self.borders { 1 } else { 0 };
-        let borders = border * (border + padding) * 2;
-        let available_width = self.max_width - borders - dividers;
+    /// The style in which to display a row.
+    fn style_for(&self, index: usize, row: &T) -> Style {
+        let style = match &self.row_style {
+            Some(row_style) => row_style(index, row),
+            None => Style::default(),
+        };
+        let Some(age) = &self.row_age else { return style };
+        let age = age(row);
+        let age_style = self.age_styles.iter().find(|(max_age, _)| age < *max_age).map(|(_, style)| *style);
+        style.or(age_style.unwrap_or_default())
+    }
+
+    /// Show a line summarizing each `interval` of rows, ex: once a minute in a long-running
+    /// monitor. `time` returns when a row happened.
+    ///
+    /// When a row arrives from a later interval, a line with the count of rows in the previous
+    /// interval is shown before it. (ex: `13:05:00–13:06:00: 42 rows`) Times are shown in UTC.
+    /// The last interval is summarized when the table is finished. Intervals without rows
+    /// aren't shown.
+    /// Like a [`raw_line`](Stream::raw_line), the summary is truncated to the table's width.
+    ///
+    /// ```
+    /// # use std::{io, time::{Duration, SystemTime}};
+    /// # use tablestream::*;
+    /// struct Request { time: SystemTime, path: String, bytes: u64 }
+    ///
+    /// let stream = Stream::stdout(vec![col!(Request: .path), col!(Request: .bytes)])
+    ///     .rollup(Duration::from_secs(60), |r: &Request| r.time)
+    ///     .rollup_sum("bytes", |r: &Request| r.bytes as f64);
+    /// ```
+    pub fn rollup<F>(mut self, interval: Duration, time: F) -> Self
+    where F: Fn(&T) -> SystemTime + 'static
+    {
+        self.rollup = Some(Rollup::new(interval, Box::new(time)));
+        self
+    }
 
+    /// Include the sum of `value` over each interval in its [`rollup`](Stream::rollup) line.
+    /// (ex: `13:05:00–13:06:00: 42 rows, bytes: 81920`)
+    ///
+    /// Has no effect unless called after [`Stream::rollup`].
+    pub fn rollup_sum<F>(mut self, label: &str, value: F) -> Self
+    where F: Fn(&T) -> f64 + 'static
+    {
+        if let Some(rollup) = &mut self.rollup {
+            rollup.add_sum(label, Box::new(value));
+        }
+        self
+    }
 
-        // First attempt:
-        // Simple calculation: Just give every column its max width.
-        let col_width = |c: &Column<T>| { 
-            let mut width = max(
-                c.max_width,
-                c.header.as_ref().map(|h| h.len()).unwrap_or(0)
-            );
-            width = max(width, c.min_width);
-            width
+    /// Display at most `rows` rows per second. (default: unlimited)
+    ///
+    /// If rows arrive faster than this, the Stream waits before displaying each one, which
+    /// also slows down whatever is producing them. This is useful when output is for humans
+    /// to read, ex: in demos and `tail`-like tools.
+    ///
+    /// Only applies when output is a terminal. (See: [`Stream::stdout`])
+    pub fn max_rows_per_second(mut self, rows: u32) -> Self {
+        self.min_row_interval = match rows {
+            0 => None,
+            rows => Some(Duration::from_secs(1) / rows),
         };
+        self
+    }
 
-        let all_max: usize = self.columns.iter().map(col_width).sum();
-        if all_max < available_width {
-            // easy mode, just give everyone their max.
+    /// Fit the table to the current terminal's width and height, so that it never scrolls.
+    ///
+    /// Rows that don't fit are summarized in a `… (N more)` row. (See: [`Stream::max_height`])
+    /// Space is left for a one-line footer and the shell prompt that follows the table.
+    /// If the terminal size can't be determined, assumes 80x24.
+    pub fn fit_screen(mut self) -> Self {
+        let (width, height) = terminal_size().unwrap_or((80, 24));
+        self.max_height = None;
+        self.fit_height = Some(height);
+        self.max_width(width)
+    }
 
-            // also: distribute extra width to each column, if we want to grow:
-            let extra_width = if self.grow.unwrap_or(false) {
-                self.width = self.max_width;
-                available_width - all_max
-            } else {
-                self.width = all_max + dividers + borders;
-                0
-            };
+    /// How many data rows we can show in a terminal of the given height.
+    fn rows_for_height(&self, height: usize) -> usize {
+        let has_headers = self.visible_columns().any(|c| c.header.is_some());
 
-            let extra_per_col = extra_width / num_cols;
-            let mut extra_last_col = extra_width % num_cols;
-            for col in self.columns.iter_mut().rev() {
-                col.width = col_width(col) + extra_per_col + extra_last_col;
-                extra_last_col = 0;
-            }
+        // Top and bottom rules, the "more" row, a footer + its rule, and the shell prompt:
+        let mut decorations = 6;
+        if self.title.is_some() && !self.title_in_border { decorations += 2; }
+        if has_headers { decorations += 2; }
+
+        max(1, height.saturating_sub(decorations))
+    }
+
+    /// Mark truncated cells with a reference (ex: `[1]`), and print their full values
+    /// as numbered footnotes below the table. (default: false)
+    ///
+    /// Note: Columns must be wider than the reference marker to show a footnote.
+    pub fn footnotes(mut self, footnotes: bool) -> Self {
+        self.footnotes = footnotes;
+        self
+    }
+
+    /// Explain the symbols (or colors) used in cells, in a legend below the table.
+    /// Entries are listed side by side, and wrapped to the table's width.
+    ///
+    /// ```
+    /// # use std::io;
+    /// # use tablestream::*;
+    /// # struct Check { name: String, status: String }
+    /// let stream = Stream::stdout(vec![col!(Check: .name), col!(Check: .status)])
+    ///     .legend(&[("✅", "passed"), ("⚠️", "flaky"), ("❌", "failed")]);
+    /// ```
+    pub fn legend(mut self, entries: &[(&str, &str)]) -> Self {
+        self.legend = entries.iter().map(|(symbol, meaning)| (symbol.to_string(), meaning.to_string())).collect();
+        self
+    }
+
+    /// Call `on_truncate` each time a cell's value is truncated to fit its column,
+    /// with the column's index and the full width of the value.
+    ///
+    /// To just count truncated cells, see [`Summary::truncated_cells`].
+    ///
+    /// ```
+    /// # use std::{cell::Cell, io, rc::Rc};
+    /// # use tablestream::*;
+    /// # struct Person { name: String }
+    /// let widest = Rc::new(Cell::new(0));
+    /// let widest_seen = widest.clone();
+    /// let stream = Stream::stdout(vec![col!(Person: .name)])
+    ///     .on_truncate(move |_column, width| widest_seen.set(widest_seen.get().max(width)));
+    /// // ...
+    /// let summary = stream.finish()?;
+    /// if summary.truncated_cells > 0 {
+    ///     eprintln!("{} cells truncated, use --wide to see up to {} columns", summary.truncated_cells, widest.get());
+    /// }
+    /// # Ok::<(), io::Error>(())
+    /// ```
+    pub fn on_truncate<F>(mut self, on_truncate: F) -> Self
+    where F: FnMut(usize, usize) + 'static
+    {
+        self.on_truncate = Some(Box::new(on_truncate));
+        self
+    }
 
+    /// Print a single row.
+    /// Note: Stream may buffer some rows before it begins output to calculate 
+    /// column sizes.
+    pub fn row(&mut self, data: T) -> io::Result<()> {
+        self.rows += 1;
+        self.validate(&data);
+        for (value, total) in &self.totals {
+            total.set(total.get() + value(&data));
+        }
+        self.first_row_at.get_or_insert_with(Instant::now);
+        if self.quiet {
             return Ok(());
         }
+        if !self.format.draws_table() {
+            return self.write_record(&data);
+        }
 
-        // What we have doesn't fit in the given width.
-        // BAD IDEA: Allocate cols according to how much we're trying to shove into them.
-        // This fails because one verbose column eats all the width, "penalizing" other columns
-        // that are displaying nice terse data.
-        // INSTEAD: "penalize" the verbose columns, by giving them the remainder after
-        // allowing the less verbose columns to use their max_width.
+        if let Some(line) = self.rollup.as_mut().and_then(|rollup| rollup.add(&data)) {
+            self.end_interval(&line)?;
+        }
+        self.check_chapter(&data)?;
 
-        for big_cols in 1..=self.columns.len() {
-            // We expect that when verbose_cols=self.columns.len(), we'll just divide 
-            // the available columns among the columns. This should only fail in
-            // pathological cases where there are just too many cols to display period.
-            if self.penalize_big_cols(big_cols) {
-                self.width = self.max_width;
-                return Ok(())
+        if let Some(same) = &self.collapse {
+            if let Some((pending, count)) = &mut self.pending {
+                if same(pending, &data) {
+                    *count += 1;
+                    return Ok(());
+                }
             }
+            return match self.pending.replace((data, 1)) {
+                Some((row, count)) => self.push_row(row, count),
+                None => Ok(()),
+            };
         }
 
-        // Should be guarded by the fact that we bump up max_width if user specifies wider columns.
-        panic!("Couldn't display {} columns worth of data in {} columns of text", self.columns.len(), self.max_width);
+        self.push_row(data, 1)
     }
 
-    /// If we can get away w/ shrinking N biggest columns, do so
-    /// and return true.
-    fn penalize_big_cols(&mut self, num_big_cols: usize) -> bool {
+    /// Print rows from `receiver` until all of its senders have disconnected.
+    ///
+    /// If no rows arrive for a while, any buffered rows are printed and the output is flushed,
+    /// so that slow producers don't leave users staring at an empty screen.
+    pub fn receive(&mut self, receiver: &mpsc::Receiver<T>) -> io::Result<()> {
+        loop {
+            match receiver.recv_timeout(IDLE_FLUSH) {
+                Ok(row) => self.row(row)?,
+                Err(RecvTimeoutError::Timeout) => self.flush()?,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+    }
+
+    /// Print any buffered rows and flush the output.
+    ///
+    /// Note: This ends the buffering phase, so column widths will be calculated from the rows
+    /// received so far.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            // As in row(), there are probably more rows to come:
+            self.grow = self.grow.or(Some(true));
+            self.write_buffer()?;
+        }
+        if let Some(view) = &mut self.view {
+            view.flush()?;
+        }
+        self.output.flush()
+    }
+
+    /// Show the rollup line for an interval, after all of its rows.
+    fn end_interval(&mut self, line: &str) -> io::Result<()> {
+        if let Some((row, count)) = self.pending.take() {
+            self.push_row(row, count)?;
+        }
+        self.raw_line(line)
+    }
+
+    /// Wait until it's time to display another row. See: [`Stream::max_rows_per_second`].
+    fn pace(&mut self) -> io::Result<()> {
+        let Some(interval) = self.min_row_interval.filter(|_| self.terminal) else { return Ok(()) };
+        if let Some(due) = self.last_row_at.map(|last| last + interval) {
+            let now = Instant::now();
+            if now < due {
+                // Show the rows so far while we wait:
+                self.output.flush()?;
+                thread::sleep(due - now);
+            }
+        }
+        self.last_row_at = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Print (or buffer) a row which was repeated `count` times.
+    fn push_row(&mut self, data: T, count: usize) -> io::Result<()> {
+        self.aggregate(&data);
+        if self.started && self.buffer.is_empty() {
+            return self.print_row(data, count);
+        }
+        
+        self.buffer.push(Buffered::Row(data, count));
+        if self.buffer.len() > self.stream_after {
+            // Prefer to grow if unspecified, to allow extra space for rows to come:
+            self.grow = self.grow.or(Some(true));
+            self.write_buffer()?;
+        } else if self.sizes_calculated {
+            // We were given a layout, no need to wait.
+            self.write_buffer()?;
+        }
+        
+        Ok(())
+    }
+
+    /// Add `row`'s values to any footer cells that aggregate them. See: [`Stream::footer_row`].
+    fn aggregate(&mut self, row: &T) {
+        if !self.footer_row || !self.format.draws_table() { return; }
+        for col in self.columns.iter_mut() {
+            let Some(footer) = col.footer.as_mut().filter(|f| f.aggregates()) else { continue };
+            footer.add(col.numbers.as_ref().and_then(|(number, _)| number(row)));
+        }
+    }
+
+    /// Collapse consecutive rows that have the same `key` into a single row, like `uniq -c`.
+    ///
+    /// This adds a column at the start of the table that shows how many times each row was
+    /// repeated. (ex: `×3`) Rows that weren't repeated leave it blank.
+    pub fn collapse_duplicates<K, F>(mut self, key: F) -> Self
+    where
+        K: PartialEq,
+        F: Fn(&T) -> K + 'static,
+    {
+        self.collapse = Some(Box::new(move |a, b| key(a) == key(b)));
+
+        let count_col = Column::counter(Counter::Repeats).right().only_for(Format::Table);
+        self.columns.insert(0, count_col);
+
+        let width = self.requested_max_width;
+        self.max_width(width)
+    }
+
+    /// Number each displayed row, in a column at the start of the table, and keep the rows so
+    /// that [`Stream::finish_numbered`] can return them. This lets interactive tools ask the
+    /// user to choose a row by its number.
+    ///
+    /// Only rows displayed in a table are numbered. (ex: not rows hidden by
+    /// [`Stream::max_height`], or written in machine-readable formats.)
+    pub fn number_rows(mut self) -> Self {
+        self.numbered = Some(vec![]);
+
+        let number_col = Column::counter(Counter::Number).header("#").right().only_for(Format::Table)
+            // Numbers may get wider after the layout is chosen. Never cut them off:
+            .degrade(&[Degrade::Wrap]);
+        self.columns.insert(0, number_col);
+
+        let width = self.requested_max_width;
+        self.max_width(width)
+    }
+
+    /// Add a column at the end of the table that shows each row's `value` as a percentage of
+    /// the total of all rows. (ex: `12.5%`)
+    ///
+    /// The total is kept as rows arrive, without a second pass. Rows buffered before output
+    /// starts (see: [`Stream::stream_after_rows`]) are shown as a percentage of the total of
+    /// every buffered row. Rows that arrive after output starts can't know the final total,
+    /// so they're shown as a percentage of the total so far.
+    ///
+    /// ```
+    /// # use std::io;
+    /// # use tablestream::*;
+    /// struct Dir { path: String, bytes: u64 }
+    ///
+    /// let stream = Stream::stdout(vec![col!(Dir: .path), col!(Dir: .bytes)])
+    ///     .percent_of_total("Share", |dir: &Dir| dir.bytes as f64);
+    /// ```
+    pub fn percent_of_total<F>(mut self, header: &str, value: F) -> Self
+    where F: Fn(&T) -> f64 + Clone + 'static
+    {
+        let total = Rc::new(Cell::new(0.0));
+        self.totals.push((Box::new(value.clone()), total.clone()));
+
+        let mut col = Column::new(move |f, row: &T| {
+            match total.get() {
+                0.0 => Ok(()),
+                total => write!(f, "{:.1}%", value(row) / total * 100.0),
+            }
+        }).header(header).right();
+        col.apply_header_case(self.header_case);
+        self.columns.push(col);
+
+        let width = self.requested_max_width;
+        self.max_width(width)
+    }
+
+    /// Split the table into chapters: each time `key` changes, close the table and start a
+    /// new one, titled with the new key. Handy for reports with one table per category.
+    ///
+    /// Every chapter has the same columns and widths, so that they line up. If the Stream has
+    /// a [`title`](Stream::title), it's used instead of the key, and may include the key as
+    /// `{chapter}`. Machine-readable formats ignore chapters.
+    ///
+    /// ```
+    /// # use std::io;
+    /// # use tablestream::*;
+    /// struct Sale { region: String, item: String, total: u32 }
+    ///
+    /// let stream = Stream::stdout(vec![col!(Sale: .item), col!(Sale: .total)])
+    ///     .chapter_by(|sale: &Sale| sale.region.clone())
+    ///     .title("Sales in {chapter}");
+    /// ```
+    pub fn chapter_by<K, F>(mut self, key: F) -> Self
+    where
+        K: fmt::Display,
+        F: Fn(&T) -> K + 'static,
+    {
+        self.chapter_key = Some(Box::new(move |row| key(row).to_string()));
+        self
+    }
+
+    /// Start a new chapter, if `row` has a different key than the last one.
+    fn check_chapter(&mut self, row: &T) -> io::Result<()> {
+        let Some(key) = &self.chapter_key else { return Ok(()) };
+        let key = key(row);
+        if self.last_chapter.as_ref() == Some(&key) { return Ok(()); }
+        let first = self.last_chapter.replace(key.clone()).is_none();
+
+        // The first chapter's title is printed with the headers:
+        if first && !self.started {
+            self.chapter = Some(key);
+            return Ok(());
+        }
+
+        // Keep previous rows in the previous chapter:
+        if let Some((row, count)) = self.pending.take() {
+            self.push_row(row, count)?;
+        }
+        match self.started {
+            true => self.start_chapter(key),
+            false => {
+                self.buffer.push(Buffered::Chapter(key));
+                Ok(())
+            },
+        }
+    }
+
+    /// Close the current table, and open a new one for the chapter named `key`.
+    fn start_chapter(&mut self, key: String) -> io::Result<()> {
+        if !self.closed {
+            self.hr(Rule::Bottom)?;
+        }
+        self.chapter = Some(key);
+        self.reopen(true)
+    }
+
+    /// Print a pre-rendered line of text between rows.
+    ///
+    /// The line spans the full width of the table (inside any borders), and bypasses column
+    /// formatting. It will be truncated if it's wider than the table.
+    pub fn raw_line(&mut self, line: &str) -> io::Result<()> {
+        if self.quiet || !self.format.draws_table() {
+            return Ok(());
+        }
+        if self.started {
+            if self.closed {
+                self.reopen(true)?;
+            }
+            self.full_line(self.direction.start(), line)?;
+            return self.grid_rule();
+        }
+
+        self.buffer.push(Buffered::Raw(line.to_string()));
+        Ok(())
+    }
+
+    /// Set a summary row, to be displayed after all other rows, below a horizontal rule.
+    ///
+    /// This is handy for showing totals which should line up with their columns.
+    /// If called more than once, only the last summary row is displayed.
+    pub fn summary_row(&mut self, data: T) {
+        self.summary_row = Some(data);
+    }
+
+    /// Display a row of footer cells below all other rows (including any summary row), with
+    /// totals that are kept up to date as rows arrive, so that you needn't keep the rows.
+    ///
+    /// ```
+    /// # use std::io;
+    /// # use tablestream::*;
+    /// struct Sale { item: String, total: f64 }
+    /// let mut stream = Stream::stdout(vec![
+    ///     col!(Sale: .item).header("Item").footer_text("Total"),
+    ///     Column::typed(|s: &Sale| s.total, NumberFormat::default().precision(2)).header("Amount").footer_sum(),
+    /// ]).footer_row();
+    /// stream.row(Sale { item: "Tea".into(), total: 3.5 })?;
+    /// stream.row(Sale { item: "Cake".into(), total: 4.25 })?;
+    /// stream.finish()?; // Total | 7.75
+    /// # Ok::<(), io::Error>(())
+    /// ```
+    ///
+    /// Each column's cell is set with [`Column::footer_text`], [`Column::footer_count`],
+    /// [`Column::footer_sum`], or [`Column::footer_avg`]. The footer row is only displayed
+    /// in tables.
+    pub fn footer_row(mut self) -> Self {
+        self.footer_row = true;
+        self
+    }
+
+    /// When the table is finished, display how many rows it had, on a line below them.
+    /// (ex: `42 rows`) Like [`footer_row`](Stream::footer_row), this counts rows as they
+    /// arrive, so that you needn't keep them.
+    ///
+    /// ```
+    /// # use std::io;
+    /// # use tablestream::*;
+    /// struct City { name: String }
+    /// let mut stream = Stream::stdout(vec![col!(City: .name)]).count_rows();
+    /// stream.row(City { name: "Tokyo".into() })?;
+    /// stream.finish()?; // 1 row
+    /// # Ok::<(), io::Error>(())
+    /// ```
+    ///
+    /// To count rows in a column's footer cell instead, see: [`Column::footer_count`].
+    /// A [`footer`](Stream::footer) replaces this line. Machine-readable formats leave it out.
+    pub fn count_rows(mut self) -> Self {
+        self.count_rows = true;
+        self
+    }
+
+    /// Pin a row to the top or bottom of the table, regardless of when it's submitted.
+    ///
+    /// Pinned rows are displayed in the order they were pinned, separated from other rows
+    /// by a horizontal rule. Rows pinned to the bottom are held until the table is finished,
+    /// and are displayed above any [`summary_row`](Stream::summary_row).
+    /// Rows can only be pinned to the top before output has started.
+    pub fn pin(&mut self, data: T, position: Position) -> io::Result<()> {
+        match position {
+            Position::Top if self.started => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "can't pin a row to the top of a table that has already been displayed",
+            )),
+            Position::Top => {
+                self.pinned_top.push(data);
+                Ok(())
+            },
+            Position::Bottom => {
+                self.pinned_bottom.push(data);
+                Ok(())
+            },
+        }
+    }
+
+    /// Print only the title and headers of the table, without any rows.
+    ///
+    /// Useful for previewing which columns a command will output. Columns will be sized to
+    /// fit their headers and min widths, unless a [`Layout`] was given.
+    pub fn print_schema(mut self) -> io::Result<()> {
+        if !self.sizes_calculated {
+            self.allocate_widths()?;
+        }
+        self.print_headers(Rule::Top)
+    }
+
+    /// Add a column to the table.
+    ///
+    /// If output has already started, the current table is closed and a continuation table
+    /// with the new set of columns (and the same title) is started below it.
+    pub fn add_column(&mut self, mut column: Column<T>) -> io::Result<()> {
+        column.use_format(self.format);
+        column.apply_header_case(self.header_case);
+        if let Some(placeholder) = &self.none_as {
+            column.none_as.get_or_insert_with(|| placeholder.clone());
+        }
+        self.columns.push(column);
+        let width = self.requested_max_width;
+        self.set_max_width(width);
+        if !self.format.draws_table() {
+            return Ok(());
+        }
+
+        if !self.started {
+            return Ok(());
+        }
+
+        // Re-allocate widths, using what we've seen so far:
+        for col in self.columns.iter_mut() {
+            col.max_width = max(col.max_width, col.limit(col.widest));
+            col.width = 0;
+        }
+        self.allocate_widths()?;
+        self.print_headers(Rule::Middle)
+    }
+
+    fn write_buffer(&mut self) -> io::Result<()> {
+        self.calc_sizes()?;
+
+        if !self.started {
+            self.started = true;
+            self.print_headers(Rule::Top)?;
+
+            for (i, col) in self.columns.iter().enumerate() {
+                if let Some(prefix) = &col.prefix {
+                    self.notes.push(format!("{}: values start with \"{}\"", col.name(i), prefix));
+                }
+            }
+
+            let pinned = mem::take(&mut self.pinned_top);
+            if !pinned.is_empty() {
+                self.write_pinned(pinned)?;
+                self.hr(Rule::Middle)?;
+            }
+        }
+
+        let mut buffer = mem::take(&mut self.buffer).into_iter();
+        while let Some(item) = buffer.next() {
+            let written = match item {
+                Buffered::Row(row, count) => self.print_row(row, count),
+                Buffered::Raw(line) => {
+                    self.full_line(self.direction.start(), &line).and_then(|_| self.grid_rule())
+                },
+                Buffered::Chapter(key) => self.start_chapter(key),
+            };
+            if let Err(err) = written {
+                // Keep the rest, so that a failed row (ex: Degrade::Error) doesn't lose them:
+                self.buffer = buffer.collect();
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write rows that were pinned with [`Stream::pin`], below a rule.
+    fn write_pinned(&mut self, rows: Vec<T>) -> io::Result<()> {
+        if rows.is_empty() { return Ok(()); }
+
+        self.hr(Rule::Middle)?;
+        for row in rows {
+            self.write_row(Some(&row), RowInfo::default())?;
+        }
+        Ok(())
+    }
+
+    /// Print the title and headers, starting with a rule of the given kind.
+    fn print_headers(&mut self, first: Rule) -> io::Result<()> {
+        let title = match &self.title {
+            Some(title) => Some(self.expand_template(title)),
+            None => self.chapter.clone(),
+        };
+        let caption = title.as_deref().filter(|_| self.title_in_border);
+        let captioned = self.hr_captioned(first, title.is_none() || caption.is_some(), caption)?;
+
+        if let Some(title) = title.filter(|_| !captioned) {
+            if self.title_wrap {
+                for line in width::wrap(&title, self.line_width()) {
+                    self.full_line(Alignment::Center, line)?;
+                }
+            } else {
+                self.full_line(Alignment::Center, &title)?;
+            }
+            self.hr(Rule::Middle)?;
+        }
+
+        let has_headers = self.visible_columns().any(|c| c.header.is_some());
+        if has_headers {
+            self.border_left()?;
+            let divider = self.decorations.divider.as_str();
+            let order = self.display_order();
+            let mut line_width = self.borders_width();
+            let mut first = true;
+            let mut names = vec![];
+            for &index in &order {
+                let col = &self.columns[index];
+                if !col.visible() { continue; }
+                let name = col.header.as_deref().unwrap_or("");
+                if self.key_values {
+                    names.push(name);
+                    continue;
+                }
+
+                if !first {
+                    write!(&mut self.output, "{}", divider)?;
+                    line_width += str_width(divider);
+                }
+                first = false;
+
+                line_width += Alignment::Center.write(&mut self.output, col.width, name)?;
+            }
+            // Key/value lists line up their headers with keys and values, without leaders:
+            if let Some((value, keys)) = names.split_last() {
+                let width = self.line_width();
+                line_width += write_leaders(&mut self.output, width, &keys.join(" "), value, ' ')?;
+            }
+            self.border_right()?;
+            self.after_dividers = !self.key_values;
+            self.check_line("header line", line_width)?;
+
+            // Tables can be reopened (ex: by add_column), but the view is one CSV file:
+            let view_started = mem::replace(&mut self.view_started, true);
+            if let Some(view) = self.view.as_mut().filter(|_| !view_started) {
+                let columns = &self.columns;
+                let headers = order.iter()
+                    .map(|&index| &columns[index])
+                    .filter(|c| c.visible())
+                    .map(|c| c.header.as_deref().unwrap_or(""));
+                csv::write_record(view, headers)?;
+            }
+            self.hr(Rule::Header)?;
+        }
+
+        Ok(())
+    }
+
+    fn hr(&mut self, rule: Rule) -> io::Result<()> {
+        self.hr_above(rule, rule != Rule::Bottom)
+    }
+
+    /// Draw a rule. `dividers_below` says whether the next line will have column dividers.
+    fn hr_above(&mut self, rule: Rule, dividers_below: bool) -> io::Result<()> {
+        self.hr_captioned(rule, dividers_below, None)?;
+        Ok(())
+    }
+
+    /// Draw a rule, with a caption inside it if possible. Returns whether it was captioned.
+    fn hr_captioned(&mut self, rule: Rule, dividers_below: bool, caption: Option<&str>) -> io::Result<bool> {
+        // Two rules in a row (ex: when there are no rows) look like a mistake:
+        if self.after_rule || !self.theme.draws(rule) { return Ok(false); }
+        if rule == Rule::Header && !self.frame.header_rule { return Ok(false); }
+        self.after_rule = true;
+
+        let mut line = String::new();
+        self.theme.write_rule(&mut line, rule, self.width, self.frame.outer);
+
+        let widths: Vec<usize> = self.display_order().into_iter()
+            .map(|index| &self.columns[index])
+            .filter(|c| c.visible())
+            .map(|c| c.width)
+            .collect();
+        let adjoining = (self.after_dividers, dividers_below);
+        match self.divided_rules {
+            Some(false) => {},
+            Some(true) if !self.theme.has_junctions() => {
+                let theme = self.theme.clone().junctions(['+'; 5]);
+                theme.join_rule(&mut line, rule, &self.decorations, &widths, adjoining);
+            },
+            _ => self.theme.join_rule(&mut line, rule, &self.decorations, &widths, adjoining),
+        }
+        self.after_dividers = false;
+        let captioned = match caption {
+            Some(caption) => self.theme.caption_rule(&mut line, caption),
+            None => false,
+        };
+
+        writeln!(&mut self.output, "{}", line)?;
+        Ok(captioned)
+    }
+
+    fn border_left(&mut self) -> io::Result<()> {
+        self.after_rule = false;
+        self.after_dividers = false;
+        write!(&mut self.output, "{}", self.decorations.left)
+    }
+    fn border_right(&mut self) -> io::Result<()> {
+        writeln!(&mut self.output, "{}", self.decorations.right)
+    }
+
+    /// The width available for a line of text that spans the full width of the table.
+    fn line_width(&self) -> usize {
+        self.width.saturating_sub(self.borders_width())
+    }
+
+    /// Write a line of text that spans the full width of the table (inside any borders).
+    fn full_line(&mut self, alignment: Alignment, text: &str) -> io::Result<()> {
+        let line_width = self.line_width();
+
+        self.border_left()?;
+        let written = alignment.write(&mut self.output, line_width, text)?;
+        self.border_right()?;
+        self.check_line("full-width line", self.borders_width() + written)
+    }
+
+    /// In formats with a rule below every row, draw it.
+    fn grid_rule(&mut self) -> io::Result<()> {
+        if self.format != Format::Rst { return Ok(()); }
+        self.hr(Rule::Middle)
+    }
+
+    /// Check that a line we've just written spans exactly the width of the table.
+    /// Violations are a bug in our layout code. They panic in debug builds, and are
+    /// returned as errors when `strict_layout` is set.
+    fn check_line(&self, what: &str, width: usize) -> io::Result<()> {
+        if width == self.width { return Ok(()); }
+
+        let message = format!("{} is {} columns wide, but the table is {} columns wide", what, width, self.width);
+        if self.strict_layout {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+        if cfg!(debug_assertions) {
+            panic!("tablestream layout bug: {}", message);
+        }
+        Ok(())
+    }
+
+    fn print_row(&mut self, row: T, count: usize) -> io::Result<()> {
+        if self.suppress_rows {
+            return Ok(());
+        }
+
+        if let Some(max_height) = self.max_height {
+            if self.rows_printed >= max_height {
+                self.rows_hidden += 1;
+                return self.measure_hidden(&row, RowInfo::data(count, 0));
+            }
+        }
+        self.rows_printed += 1;
+
+        self.pace()?;
+        self.write_row(Some(&row), RowInfo::data(count, self.rows_printed))?;
+        if let Some(numbered) = &mut self.numbered {
+            numbered.push(row);
+        }
+        Ok(())
+    }
+
+    /// Measure a row we won't display, so that [`Summary::untruncated_width`] includes it.
+    fn measure_hidden(&mut self, row: &T, info: RowInfo) -> io::Result<()> {
+        let buf = &mut self.str_buf;
+        for col in self.columns.iter_mut().filter(|c| c.visible()) {
+            buf.clear();
+            let width = col.render(row, info, buf).to_io()?;
+            let width = col.fill_empty(buf, width) + usize::from(info.validate && col.invalid(row));
+            col.widest = max(col.widest, width);
+        }
+        Ok(())
+    }
+
+    /// Run column validators for this row, noting any errors.
+    fn validate(&mut self, row: &T) {
+        for (i, col) in self.columns.iter().enumerate() {
+            let Some(validator) = &col.validator else { continue };
+            if let Err(message) = validator(row) {
+                let error = ValidationError {
+                    row: self.rows,
+                    column: col.name(i),
+                    message,
+                };
+                self.notes.push(format!("! Row {}, {}: {}", error.row, error.column, error.message));
+                self.validation_errors.push(error);
+            }
+        }
+    }
+
+    /// Write `row`, or the footer row if it's `None`.
+    fn write_row(&mut self, row: Option<&T>, info: RowInfo) -> io::Result<()> {
+        if self.closed {
+            self.reopen(true)?;
+        }
+        self.after_rule = false;
+        self.after_dividers = !self.key_values;
+        let style = match (self.colors, row) {
+            (true, Some(row)) => self.style_for(self.rows_displayed, row),
+            _ => Style::default(),
+        };
+        self.rows_displayed += 1;
+        let order = self.display_order();
+        let buf = &mut self.str_buf;
+
+        // Render each visible cell, in display order:
+        let mut visible = 0;
+        for &index in &order {
+            let col = &mut self.columns[index];
+            if !col.visible() { continue; }
+
+            if self.cells.len() <= visible {
+                self.cells.push(String::new());
+                self.highlights.push(Style::default());
+            }
+            let cell = &mut self.cells[visible];
+
+            buf.clear();
+            let mut width = match row {
+                Some(row) => col.render(row, info, buf).to_io()?,
+                None => col.render_footer(buf),
+            };
+            if self.colors {
+                self.highlights[visible] = col.highlight_for(buf);
+            }
+            if row.is_some() {
+                width = col.fill_empty(buf, width);
+            }
+            visible += 1;
+            if row.is_some_and(|row| info.validate && col.invalid(row)) {
+                buf.insert(0, '!');
+                width += 1;
+            }
+            col.widest = max(col.widest, width);
+
+            cell.clear();
+            let overflow = col.overflow(self.wrap);
+            if width <= col.width || overflow == Degrade::Wrap {
+                cell.push_str(buf);
+                continue;
+            }
+            if overflow == Degrade::Error {
+                let row = match info.number {
+                    0 => String::new(),
+                    number => format!("row {}, ", number),
+                };
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!(
+                    "{}{}: a value is {} columns wide, but the column is only {} columns wide",
+                    row, col.name(index), width, col.width,
+                )));
+            }
+
+            self.truncated_cells += 1;
+            col.truncated_cells += 1;
+            col.max_overflow = max(col.max_overflow, width - col.width);
+            if let Some(on_truncate) = &mut self.on_truncate {
+                on_truncate(index, width);
+            }
+            if self.footnotes {
+                let marker = format!("[{}]", self.footnote_count + 1);
+                // If the column is too narrow for a marker, we can't reference a footnote. :(
+                if col.width > marker.len() {
+                    self.footnote_count += 1;
+                    self.notes.push(format!("{} {}", marker, buf));
+
+                    cell.push_str(&col.truncate.apply(buf, col.width - marker.len(), ""));
+                    cell.push_str(&marker);
+                    continue;
+                }
+            }
+            let marker = match col.shrunk && self.truncation_marker.is_empty() {
+                true => "…",
+                false => &self.truncation_marker,
+            };
+            cell.push_str(&col.truncate.apply(buf, col.width, marker));
+        }
+
+        // Wrap cells that need it, then write as many lines as the tallest cell:
+        let (all_columns, wrap) = (&self.columns, self.wrap);
+        let columns: Vec<_> = order.iter()
+            .map(|&index| &all_columns[index])
+            .filter(|c| c.visible())
+            .collect();
+        let lines: Vec<Vec<&str>> = columns.iter().zip(&self.cells).map(|(col, cell)| {
+            if col.overflow(wrap) == Degrade::Wrap && str_width(cell) > col.width {
+                width::wrap(cell, col.width)
+            } else {
+                vec![cell.as_str()]
+            }
+        }).collect();
+        let colors = self.colors;
+        let styles: Vec<Style> = columns.iter().zip(&self.highlights).map(|(col, highlight)| {
+            if !colors { return style; }
+            let style = match (&col.style, row) {
+                (Some(cell_style), Some(row)) => cell_style(row).or(style),
+                _ => style,
+            };
+            highlight.or(style)
+        }).collect();
+        // Key/value lists are always one line per row, with unwrapped cells:
+        let key_value = match self.key_values {
+            true => self.cells[..columns.len()].split_last().map(|(value, keys)| (keys.join(" "), value.as_str())),
+            false => None,
+        };
+        let height = match key_value {
+            Some(_) => 1,
+            None => lines.iter().map(Vec::len).max().unwrap_or(1),
+        };
+
+        if let Some(view) = &mut self.view {
+            let displayed: Vec<String> = columns.iter().zip(&lines).map(|(col, cell_lines)| {
+                let cell_lines = cell_lines.iter().map(|line| {
+                    let line = width::printable(line);
+                    width::truncate(&line, col.width).0.to_string()
+                });
+                cell_lines.collect::<Vec<_>>().join("\n")
+            }).collect();
+            csv::write_record(view, displayed.iter().map(String::as_str))?;
+        }
+
+        let borders_width = self.borders_width();
+        let out = &mut self.output;
+        let decorations = &self.decorations;
+        let divider = decorations.divider.as_str();
+        let start = self.direction.start();
+        let fill_background = self.fill_background;
+        for line in 0..height {
+            write!(out, "{}", decorations.left)?;
+
+            let mut line_width = 0;
+            if let Some((key, value)) = &key_value {
+                style.write_start(out)?;
+                line_width += write_leaders(out, self.width.saturating_sub(borders_width), key, value, self.leader)?;
+                style.write_end(out)?;
+            } else {
+                for (pos, ((col, cell_lines), style)) in columns.iter().zip(&lines).zip(&styles).enumerate() {
+                    if pos > 0 {
+                        write!(out, "{}", divider)?;
+                        line_width += str_width(divider);
+                    }
+                    let alignment = col.alignment.unwrap_or(start);
+                    // Lines below a cell's last line are just blank, not filled:
+                    let (value, fill) = match cell_lines.get(line) {
+                        Some(value) => (*value, col.fill),
+                        None => ("", ' '),
+                    };
+                    // Style each cell separately, so that dividers aren't styled:
+                    line_width += alignment.write_styled(out, col.width, value, fill, *style, fill_background)?;
+                }
+            }
+
+            writeln!(out, "{}", decorations.right)?;
+
+            let line_width = borders_width + line_width;
+            if line_width != self.width {
+                return self.check_line("row", line_width);
+            }
+        }
+
+        self.grid_rule()
+    }
+
+    /// Measure `sample` rows as if they were among the first rows of the table, but don't
+    /// display them.
+    ///
+    /// Column widths are still calculated when output starts, from these rows and any that
+    /// were buffered. So, tools whose data has a known shape can pass representative (or
+    /// widest-possible) rows, then use [`Stream::no_buffer`] to display real rows as soon as
+    /// they arrive, without the layout depending on whichever row happened to come first.
+    ///
+    /// Has no effect after widths have been calculated. See also: [`Stream::plan`].
+    pub fn sample_only(&mut self, sample: &[T]) -> io::Result<()> {
+        if self.sizes_calculated { return Ok(()); }
+
+        let rows = sample.iter().map(|row| (row, RowInfo::default()));
+        self.sampled_rows += measure(&mut self.columns, rows, &mut self.str_buf, self.wrap)?;
+        Ok(())
+    }
+
+    /// Calculate the widths that we would give each column if `sample` were the first rows
+    /// of the table, without writing any output.
+    ///
+    /// You can inspect or modify the resulting [`Layout`], then apply it to a Stream with
+    /// [`Stream::layout`]. Rows passed to `plan()` are not displayed.
+    pub fn plan(&mut self, sample: &[T]) -> io::Result<Layout> {
+        if self.sizes_calculated {
+            return Ok(self.current_layout());
+        }
+
+        let rows = sample.iter().map(|row| (row, RowInfo::default()));
+        measure(&mut self.columns, rows, &mut self.str_buf, self.wrap)?;
+        self.allocate_widths()?;
+        let layout = self.current_layout();
+
+        // Forget what we've measured, so that we'll measure our real rows later:
+        self.sizes_calculated = false;
+        self.width = 0;
+        for col in self.columns.iter_mut() {
+            col.width = 0;
+            col.max_width = 0;
+            col.width_sum = 0;
+            col.values = 0;
+            col.has_text = false;
+            col.shrunk = false;
+            col.auto_hidden = false;
+            col.prefix = None;
+        }
+
+        Ok(layout)
+    }
+
+    /// Use a predetermined layout instead of calculating column widths from the first rows.
+    ///
+    /// Since widths are already known, rows will be output immediately instead of being buffered.
+    /// Columns which don't have a width in the layout will use their `min_width`.
+    pub fn layout(mut self, layout: Layout) -> Self {
+        for (i, col) in self.columns.iter_mut().enumerate() {
+            col.width = match col.visible() {
+                true => layout.widths.get(i).copied().unwrap_or(col.min_width),
+                false => 0,
+            };
+        }
+        self.width = self.visible_columns().map(|c| c.width).sum::<usize>() + self.decorations_width();
+        self.sizes_calculated = true;
+        self
+    }
+
+    fn current_layout(&self) -> Layout {
+        Layout {
+            widths: self.columns.iter().map(|c| c.width).collect(),
+        }
+    }
+
+    /// Column indexes, in the order they're displayed.
+    fn display_order(&self) -> Vec<usize> {
         let num_cols = self.columns.len();
-        let padding = if self.padding { 1 } else { 0 };
-        let dividers = (num_cols - 1) * (1 + 2*padding);
-        let border = if self.borders { 1 } else { 0 };
-        let borders = border * (border + padding) * 2;
-        let available_width = self.max_width - borders - dividers;
+        (0..num_cols).map(|i| {
+            let i = self.direction.index(i, num_cols);
+            self.order.get(i).copied().unwrap_or(i)
+        }).collect()
+    }
+
+    /// The width of the dividers and borders in each line.
+    fn decorations_width(&self) -> usize {
+        self.dividers_width() + self.borders_width()
+    }
+
+    /// The width of all dividers between columns in each line.
+    fn dividers_width(&self) -> usize {
+        self.dividers_for(self.visible_columns().count())
+    }
+
+    fn dividers_for(&self, num_cols: usize) -> usize {
+        num_cols.saturating_sub(1) * str_width(&self.decorations.divider)
+    }
+
+    /// The width of the left and right borders (and their padding).
+    fn borders_width(&self) -> usize {
+        str_width(&self.decorations.left) + str_width(&self.decorations.right)
+    }
+
+    /// Columns that will be displayed in the table.
+    fn visible_columns(&self) -> impl Iterator<Item = &Column<T>> {
+        self.columns.iter().filter(|c| c.visible())
+    }
+
+    fn visible_columns_mut(&mut self) -> impl Iterator<Item = &mut Column<T>> {
+        self.columns.iter_mut().filter(|c| c.visible())
+    }
+
+    fn calc_sizes(&mut self) -> io::Result<()> {
+        if self.sizes_calculated { return Ok(()); }
+
+        for col in self.columns.iter_mut() {
+            col.auto_hidden = false;
+        }
+        let suppress_rows = self.suppress_rows;
+        let mut number = self.rows_printed;
+        let rows = self.buffer.iter().filter_map(|item| match item {
+            Buffered::Row(row, count) if !suppress_rows => {
+                number += 1;
+                Some((row, RowInfo::data(*count, number)))
+            },
+            _ => None,
+        });
+        let extra_rows = self.pinned_top.iter()
+            .chain(self.pinned_bottom.iter())
+            .chain(self.summary_row.as_ref())
+            .map(|row| (row, RowInfo::default()));
+        let rows = rows.chain(extra_rows);
+        let num_rows = measure(&mut self.columns, rows, &mut self.str_buf, self.wrap)?;
+        if self.footer_row {
+            for col in self.columns.iter_mut().filter(|c| c.visible()) {
+                let Some(footer) = &col.footer else { continue };
+                let width = col.limit(str_width(&footer.text(col.number_writer())));
+                col.max_width = max(col.max_width, width);
+            }
+        }
+
+        if num_rows + self.sampled_rows > 0 {
+            for col in self.columns.iter_mut().filter(|c| c.hide_if_empty && c.values == 0) {
+                col.auto_hidden = true;
+            }
+            if let Some((threshold, sparse)) = self.sparse {
+                self.handle_sparse_columns(num_rows + self.sampled_rows, threshold, sparse);
+            }
+            // Line up the digits of numeric columns, unless told otherwise:
+            for col in self.columns.iter_mut().filter(|c| c.alignment.is_none() && c.values > 0 && !c.has_text) {
+                col.alignment = Some(Alignment::Right);
+            }
+        }
+
+        if self.order_by_width {
+            self.order = sort_by_width(&self.columns);
+        }
+        self.allocate_widths()
+    }
+
+    /// Hide or shrink columns with values in fewer than `threshold` of `num_rows`.
+    fn handle_sparse_columns(&mut self, num_rows: usize, threshold: f64, sparse: Sparse) {
+        let min_values = threshold * num_rows as f64;
+        let mut hidden = vec![];
+        for (index, col) in self.columns.iter_mut().enumerate() {
+            if !col.visible() || col.values as f64 >= min_values { continue; }
+            match sparse {
+                Sparse::Hide => {
+                    col.auto_hidden = true;
+                    hidden.push(col.name(index));
+                },
+                Sparse::Shrink => {
+                    let width = col.header.as_deref().map_or(1, str_width).max(1);
+                    col.shrunk = true;
+                    col.max_width = min(col.max_width, width);
+                    col.longest_word = min(col.longest_word, width);
+                },
+            }
+        }
+        if self.note_dropped && !hidden.is_empty() {
+            self.notes.push(format!("Mostly empty: {}", hidden.join(", ")));
+        }
+    }
+
+    /// Allocate widths to columns, based on what we've measured.
+    fn allocate_widths(&mut self) -> io::Result<()> {
+        self.distribute_widths();
+
+        let columns: usize = self.visible_columns().map(|c| c.width).sum();
+        self.check_line("sum of column widths (plus decorations)", columns + self.decorations_width())
+    }
+
+    fn distribute_widths(&mut self) {
+        self.sizes_calculated = true;
+
+        if let Some(height) = self.fit_height {
+            self.max_height = Some(self.rows_for_height(height));
+        }
+
+        // Hide columns that would rather be hidden than squeezed below their min_width,
+        // lowest priority first:
+        let mut dropped = vec![];
+        loop {
+            let min_widths: usize = self.visible_columns().map(|c| c.min_width).sum();
+            if min_widths + self.decorations_width() <= self.max_width { break; }
+            let droppable = self.columns.iter().enumerate().rev().filter(|(_, c)| c.visible() && c.can_hide());
+            match droppable.min_by_key(|(_, c)| c.priority.unwrap_or(0)) {
+                Some((index, _)) => {
+                    self.columns[index].auto_hidden = true;
+                    dropped.push(self.columns[index].name(index));
+                },
+                None => break,
+            }
+        }
+        if self.note_dropped && !dropped.is_empty() {
+            self.notes.push(format!("Hidden to fit: {}", dropped.join(", ")));
+        }
+
+        let num_cols = self.visible_columns().count();
+        let decorations = self.decorations_width();
+        let available_width = self.max_width.saturating_sub(decorations);
+
+        // Columns with a width_pct are treated like fixed-width columns from here on:
+        for col in self.visible_columns_mut() {
+            col.target_width = col.width_pct.map(|pct| (available_width as f32 * pct / 100.0) as usize);
+        }
+
+        if num_cols == 0 {
+            self.width = decorations;
+            return;
+        }
+
+        // First attempt:
+        // Simple calculation: Just give every column its max width.
+        let col_width = |c: &Column<T>, step: usize| { 
+            let mut width = max(
+                c.max_width,
+                c.header.as_deref().map(str_width).unwrap_or(0)
+            );
+            width = max(width, c.min());
+            max(c.limit(width.div_ceil(step).saturating_mul(step)), c.min())
+        };
+
+        let mut step = self.width_step;
+        // (Saturating, since a huge step can round widths up to usize::MAX.)
+        let mut all_max = self.visible_columns().map(|c| col_width(c, step)).fold(0, usize::saturating_add);
+        if all_max >= available_width && step > 1 {
+            // Rounding up is only worth it if we have room to spare:
+            step = 1;
+            all_max = self.visible_columns().map(|c| col_width(c, step)).sum();
+        }
+        if all_max < available_width {
+            // easy mode, just give everyone their max.
+
+            // also: distribute extra width to each column, if we want to grow:
+            // (by flex weight. Columns with a max_width don't grow past it.)
+            // (In u128, so that huge flex weights can't overflow.)
+            let total_weight: u128 = self.visible_columns().map(|c| c.flex_weight() as u128).sum();
+            let target_width = match self.grow.unwrap_or(false) {
+                true => self.max_width,
+                false => max(all_max + decorations, self.min_table_width),
+            };
+            let extra_width = if total_weight > 0 {
+                self.width = target_width;
+                target_width - decorations - all_max
+            } else {
+                self.width = all_max + decorations;
+                0
+            };
+
+            let mut remainder = extra_width;
+            let mut cols = self.visible_columns_mut().collect::<Vec<_>>();
+            for col in cols.iter_mut() {
+                let weighted = extra_width as u128 * col.flex_weight() as u128;
+                let share = weighted.checked_div(total_weight).unwrap_or(0) as usize;
+                col.width = col_width(col, step) + share;
+                remainder -= share;
+            }
+            // Any remainder from rounding goes to the last column that grows:
+            if let Some(col) = cols.iter_mut().rev().find(|c| c.flex_weight() > 0) {
+                col.width += remainder;
+            }
+
+            return;
+        }
+
+        // What we have doesn't fit in the given width.
+        // BAD IDEA: Allocate cols according to how much we're trying to shove into them.
+        // This fails because one verbose column eats all the width, "penalizing" other columns
+        // that are displaying nice terse data.
+        // INSTEAD: "penalize" the verbose columns, by giving them the remainder after
+        // allowing the less verbose columns to use their max_width.
+
+        for big_cols in 1..=num_cols {
+            // We expect that when verbose_cols=self.columns.len(), we'll just divide 
+            // the available columns among the columns. This should only fail in
+            // pathological cases where there are just too many cols to display period.
+            if self.penalize_big_cols(big_cols) {
+                // Usually max_width, unless columns were held to their own max_width:
+                self.width = self.visible_columns().map(|c| c.width).sum::<usize>() + decorations;
+                return;
+            }
+        }
+
+        // Should be guarded by the fact that we bump up max_width if user specifies wider columns.
+        // But if not (or columns can Degrade::Grow), overflow max_width rather than panicking:
+        for col in self.visible_columns_mut() {
+            col.width = col.min();
+        }
+        self.width = self.visible_columns().map(|c| c.width).sum::<usize>() + decorations;
+    }
+
+    /// If we can get away w/ shrinking N biggest columns, do so
+    /// and return true.
+    fn penalize_big_cols(&mut self, num_big_cols: usize) -> bool {
+        let available_width = self.max_width.saturating_sub(self.decorations_width());
 
-        let mut col_refs: Vec<_> = self.columns.iter_mut().collect();
+        let mut col_refs: Vec<_> = self.visible_columns_mut().collect();
+        let num_cols = col_refs.len();
         col_refs.sort_by_key(|c| c.width_sum); // sort "big" cols to the end:
-        let (small_cols, big_cols) = col_refs.split_at_mut(num_cols - num_big_cols);
+        let (small_cols, big_cols) = col_refs.split_at_mut(num_cols.saturating_sub(num_big_cols));
 
         let needed_width: usize = 
-            small_cols.iter().map(|c| max(c.min_width, c.max_width)).sum::<usize>()
-            + big_cols.iter().map(|c| c.min_width).sum::<usize>();
+            small_cols.iter().map(|c| max(c.min(), c.limit(c.max_width))).sum::<usize>()
+            + big_cols.iter().map(|c| c.min()).sum::<usize>();
 
         if needed_width > available_width {
             return false
@@ -413,95 +2067,1002 @@ impl <T, Out: Write> Stream<T, Out> {
         // Small cols all get their max width. Yay!
         let mut remaining_width = available_width;
         for col in small_cols.iter_mut() {
-            col.width = max(col.min_width, col.max_width);
-            remaining_width -= col.width;
+            col.width = max(col.min(), col.limit(col.max_width));
+            remaining_width = remaining_width.saturating_sub(col.width);
         }
 
         // Big cols get assigned the remaining sizes.
 
         // First pass, try assigning widths w/ simple algorithm.  If the column has a min_width that is
-        // larger, subtract the width from available cols, which we'll reallocate on the 2nd pass.
+        // larger (or a max_width that is smaller), subtract the width from available cols, which
+        // we'll reallocate on the 2nd pass.
         let mut big_cols_left = num_big_cols;
         for col in big_cols.iter_mut() {
             let cols_per_big_col = remaining_width / big_cols_left;
-            if cols_per_big_col < col.min_width {
-                col.width = col.min_width;
-                remaining_width -= col.width;
+            let width = max(col.limit(cols_per_big_col), col.min());
+            if width != cols_per_big_col {
+                col.width = width;
+                remaining_width = remaining_width.saturating_sub(col.width);
                 big_cols_left -= 1;
             }
         }
 
-        // Second pass: allocate remaining cols:
-        if big_cols_left > 0 {
-            let cols_per_big_col = remaining_width / big_cols_left;
-            for col in big_cols.iter_mut() {
-                if col.width > 0 { continue; } // already calculated.
-                col.width = cols_per_big_col;
-            }   
-            
-            remaining_width -= big_cols_left * cols_per_big_col;
+        // Second pass: allocate remaining cols:
+        if let Some(cols_per_big_col) = remaining_width.checked_div(big_cols_left) {
+            for col in big_cols.iter_mut() {
+                if col.width > 0 { continue; } // already calculated.
+                col.width = cols_per_big_col;
+            }   
+            
+            remaining_width = remaining_width.saturating_sub(big_cols_left * cols_per_big_col);
+
+            // If we have any left, put it in the biggest column:
+            if remaining_width > 0 {
+                // (unless that would take it past its max_width)
+                let biggest = big_cols.iter().rposition(|c| c.limit(usize::MAX) >= c.width + remaining_width);
+                if let Some(col) = biggest.or(big_cols.len().checked_sub(1)).map(|i| &mut big_cols[i]) {
+                    col.width += remaining_width;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Write out any buffered rows, and the "more rows" indicator if we hit max_height.
+    fn finish_rows(&mut self) -> io::Result<()> {
+        if let Some((row, count)) = self.pending.take() {
+            self.push_row(row, count)?;
+        }
+        if let Some(line) = self.rollup.as_mut().and_then(Rollup::take) {
+            self.raw_line(&line)?;
+        }
+
+        // Even an empty table gets its headers, so that rules and footers know their width:
+        if !self.buffer.is_empty() || !self.started {
+            self.write_buffer()?;
+        }
+
+        if self.rows_hidden > 0 {
+            let more = format!("… ({} more)", self.rows_hidden);
+            self.full_line(self.direction.start(), &more)?;
+        }
+
+        let pinned = mem::take(&mut self.pinned_bottom);
+        self.write_pinned(pinned)?;
+
+        if let Some(summary) = self.summary_row.take() {
+            self.hr(Rule::Middle)?;
+            self.write_row(Some(&summary), RowInfo::default())?;
+        }
+
+        if self.footer_row && self.columns.iter().any(|c| c.footer.is_some()) {
+            self.hr(Rule::Middle)?;
+            self.write_row(None, RowInfo::default())?;
+        }
+
+        if let Some(view) = &mut self.view {
+            view.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Finish writing output.
+    /// This may write any items still in the buffer,
+    /// as well as a trailing horizontal line and footer.
+    /// Returns a [`Summary`] of the table.
+    pub fn finish(mut self) -> io::Result<Summary> {
+        self.finish_table()
+    }
+
+    /// Like [`finish`](Stream::finish), but also returns the rows that were numbered by
+    /// [`Stream::number_rows`], so that the row numbered `n` is at index `n - 1`.
+    ///
+    /// ```
+    /// # use std::io;
+    /// # use tablestream::*;
+    /// # struct Task { name: String }
+    /// let mut stream = Stream::stdout(vec![col!(Task: .name)]).number_rows();
+    /// stream.row(Task { name: "Write docs".into() })?;
+    /// let (_, tasks) = stream.finish_numbered()?;
+    /// // ... ask the user for a number, then:
+    /// let chosen = &tasks[1 - 1];
+    /// # Ok::<(), io::Error>(())
+    /// ```
+    pub fn finish_numbered(mut self) -> io::Result<(Summary, Vec<T>)> {
+        let summary = self.finish_table()?;
+        Ok((summary, self.numbered.take().unwrap_or_default()))
+    }
+
+    fn finish_table(&mut self) -> io::Result<Summary> {
+        if self.quiet {
+            let count = self.row_count();
+            writeln!(&mut self.output, "{}", count)?;
+            self.notify()?;
+            return Ok(self.summary());
+        }
+
+        if !self.format.draws_table() {
+            self.finish_records(None)?;
+            return Ok(self.summary());
+        }
+
+        self.finish_rows()?;
+        if self.count_rows {
+            let count = self.row_count();
+            self.write_footer(&count)?;
+        } else {
+            self.hr(Rule::Bottom)?;
+        }
+        self.print_legend()?;
+        self.print_notes()?;
+        self.notify()?;
+
+        Ok(self.summary())
+    }
+
+    /// How many rows we've received. (ex: `42 rows`)
+    fn row_count(&self) -> String {
+        let plural = if self.rows == 1 { "" } else { "s" };
+        format!("{} row{}", self.rows, plural)
+    }
+
+    /// Send our notification, if the table took long enough to deserve one.
+    fn notify(&mut self) -> io::Result<()> {
+        if self.format != Format::Table || !self.colors { return Ok(()); }
+        let notification = match &self.notify {
+            Some((min_elapsed, notification)) if self.elapsed() >= *min_elapsed => notification,
+            _ => return Ok(()),
+        };
+        match notification {
+            Notification::Bell => write!(&mut self.output, "\x07")?,
+            Notification::Osc9(message) => {
+                // Control characters (ex: BEL) would end the message early:
+                let message: String = self.expand_template(message).chars().filter(|c| !c.is_control()).collect();
+                write!(&mut self.output, "\x1b]9;{}\x07", message)?;
+            },
+        }
+        self.output.flush()
+    }
+
+    /// Start output in a machine-readable format, if we haven't yet.
+    fn start_records(&mut self) -> io::Result<()> {
+        if self.started { return Ok(()); }
+        self.started = true;
+
+        let format = self.format;
+        let headers: Vec<_> = self.columns.iter().filter(|c| c.shown_in(format)).map(|c| {
+            c.header.as_deref().unwrap_or("")
+        }).collect();
+        let headers = export_fields(&self.columns, format, &headers);
+        let title = self.title.as_ref().map(|t| self.expand_template(t));
+        let rtl = self.direction == Direction::Rtl;
+        export::write_start(&mut self.output, format, rtl, title.as_deref(), &headers)?;
+
+        for row in mem::take(&mut self.pinned_top) {
+            self.write_record(&row)?;
+        }
+        Ok(())
+    }
+
+    /// Write a row in a machine-readable format.
+    fn write_record(&mut self, row: &T) -> io::Result<()> {
+        self.start_records()?;
+        let values = self.record_values(row);
+        let fields = export_fields(&self.columns, self.format, &values);
+        export::write_row(&mut self.output, self.format, &fields)
+    }
+
+    /// The full value of each column in `row` that we export.
+    fn record_values(&self, row: &T) -> Vec<String> {
+        let format = self.format;
+        self.columns.iter().filter(|c| c.shown_in(format)).map(|col| {
+            let writer = col.writer.as_ref();
+            Displayer{ row, writer }.to_string()
+        }).collect()
+    }
+
+    /// Write any remaining rows in a machine-readable format, and end output.
+    fn finish_records(&mut self, footer: Option<&str>) -> io::Result<()> {
+        self.start_records()?;
+        for row in mem::take(&mut self.pinned_bottom) {
+            self.write_record(&row)?;
+        }
+
+        let format = self.format;
+        let summary = self.summary_row.take().map(|row| self.record_values(&row));
+        let columns = &self.columns;
+        let summary = summary.as_ref().map(|values| export_fields(columns, format, values));
+        let footer = footer.map(|f| self.expand_template(f));
+        let num_cols = self.columns.iter().filter(|c| c.shown_in(format)).count();
+        export::write_end(&mut self.output, format, num_cols, summary.as_deref(), footer.as_deref())?;
+        self.output.flush()
+    }
+
+    fn summary(&self) -> Summary {
+        let widths = self.visible_columns().map(|c| {
+            let header_width = c.header.as_deref().map(str_width).unwrap_or(0);
+            max(max(c.widest, header_width), c.min_width)
+        });
+        let untruncated_width = widths.sum::<usize>() + self.decorations_width();
+
+        Summary {
+            rows: self.rows,
+            elapsed: self.elapsed(),
+            truncated_cells: self.truncated_cells,
+            width: if self.format.draws_table() { self.width } else { 0 },
+            untruncated_width,
+            validation_errors: self.validation_errors.clone(),
+            columns: self.columns.iter().enumerate().map(|(i, c)| ColumnStats {
+                name: c.name(i),
+                width: if c.visible() { c.width } else { 0 },
+                truncated_cells: c.truncated_cells,
+                max_overflow: c.max_overflow,
+            }).collect(),
+        }
+    }
+
+    /// Time since the first row was received.
+    fn elapsed(&self) -> Duration {
+        self.first_row_at.map(|t| t.elapsed()).unwrap_or_default()
+    }
+
+    /// Replace variables in a title or footer with their current values.
+    fn expand_template(&self, template: &str) -> String {
+        template
+            .replace("{rows}", &self.rows.to_string())
+            .replace("{elapsed}", &format!("{:.1?}", self.elapsed()))
+            .replace("{truncated}", &self.truncated_cells.to_string())
+            .replace("{chapter}", self.chapter.as_deref().unwrap_or(""))
+    }
+
+    /// Print the legend's entries side by side, in lines no wider than the table.
+    fn print_legend(&mut self) -> io::Result<()> {
+        const GAP: &str = "   ";
+        let mut lines: Vec<String> = vec![];
+        for (symbol, meaning) in &self.legend {
+            let entry = format!("{} {}", symbol, meaning);
+            match lines.last_mut() {
+                Some(line) if str_width(line) + GAP.len() + str_width(&entry) <= self.width => {
+                    line.push_str(GAP);
+                    line.push_str(&entry);
+                },
+                _ => lines.push(entry),
+            }
+        }
+        for line in &lines {
+            // A single entry may still be too wide:
+            for part in width::wrap(line, self.width) {
+                writeln!(&mut self.output, "{}", part)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn print_notes(&mut self) -> io::Result<()> {
+        for note in &self.notes {
+            writeln!(&mut self.output, "{}", note)?;
+        }
+        Ok(())
+    }
+
+    /// Like [`finish`], but adds a footer at the end as well.
+    ///
+    /// The footer may contain variables. See: [`Stream::title`].
+    pub fn footer(mut self, footer: &str) -> io::Result<Summary> {
+        if self.quiet {
+            let footer = self.expand_template(footer);
+            writeln!(&mut self.output, "{}", footer)?;
+            self.notify()?;
+            return Ok(self.summary());
+        }
+        if !self.format.draws_table() {
+            self.finish_records(Some(footer))?;
+            return Ok(self.summary());
+        }
+
+        self.finish_rows()?;
+        self.write_footer(footer)?;
+        self.print_legend()?;
+        self.print_notes()?;
+        self.notify()?;
+
+        Ok(self.summary())
+    }
+
+    /// Print a footer below the rows so far, closing the table, but allow more rows to follow.
+    ///
+    /// The next row reopens the table, below its headers. This is handy for interactive
+    /// sessions, where more results may arrive after a summary of the first ones.
+    /// Machine-readable formats ignore interim footers.
+    ///
+    /// The footer may contain variables. See: [`Stream::title`].
+    pub fn interim_footer(&mut self, footer: &str) -> io::Result<()> {
+        if self.quiet {
+            let footer = self.expand_template(footer);
+            writeln!(&mut self.output, "{}", footer)?;
+            return self.output.flush();
+        }
+        if !self.format.draws_table() {
+            return Ok(());
+        }
+
+        // A collapsed row can't continue past the footer:
+        if let Some((row, count)) = self.pending.take() {
+            self.push_row(row, count)?;
+        }
+        if !self.buffer.is_empty() || !self.started {
+            // As in flush(), there are probably more rows to come:
+            self.grow = self.grow.or(Some(true));
+            self.write_buffer()?;
+        }
+
+        self.write_footer(footer)?;
+        self.closed = true;
+        self.output.flush()
+    }
+
+    /// Write a footer line and the bottom rule.
+    fn write_footer(&mut self, footer: &str) -> io::Result<()> {
+        // Don't tack this footer onto a previous one:
+        if self.closed {
+            self.reopen(false)?;
+        }
+
+        let footer = self.expand_template(footer);
+        self.hr_above(Rule::Middle, false)?;
+        self.full_line(Alignment::Center, &footer)?;
+        self.hr(Rule::Bottom)
+    }
+
+    /// Start a new frame below an interim footer, optionally repeating the title and headers.
+    fn reopen(&mut self, headers: bool) -> io::Result<()> {
+        self.closed = false;
+        self.after_rule = false;
+        match headers {
+            true => self.print_headers(Rule::Top),
+            false => self.hr_above(Rule::Top, false),
+        }
+    }
+}
+
+impl <T> Stream<T, io::Stdout> {
+    /// Create a new table streamer that writes to standard output.
+    ///
+    /// Unlike [`Stream::new`], this checks whether output is a terminal, so that options
+    /// for terminals (like colors, with [`ColorChoice::Auto`]) apply when it is.
+    pub fn stdout(columns: Vec<Column<T>>) -> Self {
+        Self::new(io::stdout(), columns).detect_terminal()
+    }
+}
+
+impl <T, Out: Write + IsTerminal> Stream<T, Out> {
+    /// Check whether output is a terminal, for options that only apply to terminals, like
+    /// [`ColorChoice::Auto`]. Otherwise, the Stream assumes that it isn't writing to one.
+    /// ([`Stream::stdout`] does this for you.)
+    ///
+    /// ```
+    /// # use std::io;
+    /// # use tablestream::*;
+    /// # struct Person { name: String }
+    /// let stream = Stream::new(io::stderr(), vec![col!(Person: .name)]).detect_terminal();
+    /// ```
+    pub fn detect_terminal(mut self) -> Self {
+        self.terminal = self.output.is_terminal();
+        self.colors = self.color.enabled(self.terminal);
+        self
+    }
+}
+
+impl <T> Stream<T, Vec<u8>> {
+    /// Finish a table that was written to a `Vec<u8>`, and return its text, with measurements
+    /// of the text itself.
+    ///
+    /// This is handy for embedding tables in fixed-width places, like emails or code blocks:
+    /// check that the result fits, and if not, render it again with a smaller `max_width`.
+    ///
+    /// ```
+    /// # use std::io;
+    /// # use tablestream::*;
+    /// # struct Task { name: String }
+    /// # let tasks = || vec![Task { name: "Write a very long task description".into() }];
+    /// let mut max_width = 72;
+    /// let rendered = loop {
+    ///     let mut stream = Stream::new(Vec::new(), vec![col!(Task: .name)]).max_width(max_width);
+    ///     for task in tasks() {
+    ///         stream.row(task)?;
+    ///     }
+    ///     let rendered = stream.finish_rendered()?;
+    ///     if rendered.width <= 30 || max_width <= 30 { break rendered; }
+    ///     max_width = 30;
+    /// };
+    /// assert!(rendered.width <= 30);
+    /// # Ok::<(), io::Error>(())
+    /// ```
+    pub fn finish_rendered(mut self) -> io::Result<Rendered> {
+        let summary = self.finish_table()?;
+        let text = String::from_utf8(mem::take(&mut self.output))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(Rendered {
+            width: text.lines().map(str_width).max().unwrap_or(0),
+            lines: text.lines().count(),
+            text,
+            summary,
+        })
+    }
+}
+
+/// Pair exported `values` with the columns that they came from.
+/// Columns without headers are named by their position, and repeated names are numbered.
+/// (ex: `Column 2`, or `Name 2`)
+fn export_fields<'a, T, S: AsRef<str>>(columns: &'a [Column<T>], format: Format, values: &'a [S]) -> Vec<Field<'a>> {
+    let mut keys: Vec<String> = Vec::new();
+    let columns = columns.iter().enumerate().filter(|(_, c)| c.shown_in(format));
+    columns.zip(values).map(|((index, col), value)| {
+        let name = col.name(index);
+        let mut key = name.clone();
+        for n in 2.. {
+            if !keys.contains(&key) { break; }
+            key = format!("{} {}", name, n);
+        }
+        keys.push(key.clone());
+        Field { text: value.as_ref(), key, alignment: col.alignment }
+    }).collect()
+}
+
+/// Measure the width of each column's data in `rows`. Returns the number of rows measured.
+fn measure<'a, T: 'a>(
+    columns: &mut [Column<T>],
+    rows: impl Iterator<Item = (&'a T, RowInfo)>,
+    buf: &mut String,
+    wrap: bool,
+) -> io::Result<usize> {
+    let mut prefixes: Vec<Option<String>> = vec![None; columns.len()];
+    let mut num_rows = 0;
+
+    for (row, info) in rows {
+        num_rows += 1;
+        for (col, prefix) in columns.iter_mut().zip(prefixes.iter_mut()) {
+            if !col.visible() { continue; }
+            buf.clear();
+            let width = col.render(row, info, buf).to_io()?;
+            col.values += usize::from(!buf.is_empty());
+            col.has_text |= !buf.is_empty() && !is_number(buf);
+            // Leave room for the marker on invalid values:
+            let width = col.fill_empty(buf, width) + usize::from(info.validate && col.invalid(row));
+            let width = col.limit(width);
+            col.max_width = max(col.max_width, width);
+            col.width_sum += width;
+            if col.may_wrap(wrap) {
+                let longest = buf.split_whitespace().map(str_width).max().unwrap_or(0);
+                col.longest_word = max(col.longest_word, longest);
+            }
+
+            if col.elide_prefix {
+                match prefix {
+                    None => *prefix = Some(buf.clone()),
+                    Some(prefix) => {
+                        let len = common_prefix_len(prefix, buf);
+                        prefix.truncate(len);
+                    },
+                }
+            }
+        }
+    }
+
+    // Any common prefixes will not be displayed, so don't count them against the width:
+    if num_rows < 2 { return Ok(num_rows); }
+    for (col, prefix) in columns.iter_mut().zip(prefixes) {
+        let Some(mut prefix) = prefix else { continue };
+        let word_end = prefix.rfind(|c: char| !c.is_alphanumeric()).map(|i| {
+            i + prefix[i..].chars().next().map(char::len_utf8).unwrap_or(0)
+        });
+        prefix.truncate(word_end.unwrap_or(0));
+        if prefix.is_empty() { continue; }
+
+        let prefix_width = str_width(&prefix);
+        col.max_width = col.max_width.saturating_sub(prefix_width);
+        col.width_sum = col.width_sum.saturating_sub(prefix_width * num_rows);
+        col.prefix = Some(prefix);
+    }
+
+    Ok(num_rows)
+}
+
+/// Order columns from narrowest to widest, as measured, so that the column most likely to be
+/// truncated comes last. Columns with [`Column::keep_position`] stay where they are.
+/// Returns the index of the column to display at each position.
+fn sort_by_width<T>(columns: &[Column<T>]) -> Vec<usize> {
+    let mut movable: Vec<usize> = (0..columns.len()).filter(|&i| !columns[i].keep_position).collect();
+    movable.sort_by_key(|&i| max(columns[i].max_width, columns[i].min_width)); // (stable)
+    let mut movable = movable.into_iter();
+    (0..columns.len()).map(|i| match columns[i].keep_position {
+        true => i,
+        false => movable.next().unwrap_or(i),
+    }).collect()
+}
+
+/// Does `value` look like a number? (ex: `-42`, `3.14`, `1,024`, `6.02e23`, or `50%`)
+fn is_number(value: &str) -> bool {
+    let value = value.trim();
+    let value = value.strip_suffix('%').unwrap_or(value);
+    let digits: String = value.chars().filter(|&c| c != ',' && c != '_').collect();
+    digits.starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c))
+        && digits.contains(|c: char| c.is_ascii_digit())
+        && digits.parse::<f64>().is_ok()
+}
+
+/// The length (in bytes) of the common prefix of `a` and `b`.
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, a), b)| a != b)
+        .map(|((i, _), _)| i)
+        .unwrap_or_else(|| a.len().min(b.len()))
+}
+
+/// Write `key` and `value` at either end of a line `width` columns wide, joined by `leader`s.
+/// (ex: `Name ........ Cody`)
+fn write_leaders<W: Write>(out: &mut W, width: usize, key: &str, value: &str, leader: char) -> io::Result<usize> {
+    let value = width::printable(value);
+    let (value, value_width) = width::truncate(&value, width);
+    // Leave room for a leader, with a space on either side:
+    let key = width::printable(key);
+    let (key, _) = width::truncate(&key, width.saturating_sub(value_width + 3));
+    let key = key.trim_end();
+    let key_width = str_width(key);
+
+    let gap = width.saturating_sub(key_width + value_width);
+    if gap < 3 {
+        write!(out, "{}{:3$}{}", key, "", value, gap)?;
+    } else {
+        let leaders: String = std::iter::repeat_n(leader, gap - 2).collect();
+        write!(out, "{} {} {}", key, leaders, value)?;
+    }
+    Ok(key_width + gap + value_width)
+}
+
+/// Things that we may hold in our buffer until we've calculated column sizes.
+enum Buffered<T> {
+    Row(T, usize), // (row, number of times it was repeated)
+    Raw(String),
+    Chapter(String), // the key of a new chapter.
+}
+
+/// What to do with columns that are mostly empty.
+#[derive(Clone, Copy)]
+enum Sparse {
+    Hide,
+    Shrink,
+}
+
+/// Get the (width, height) of the current terminal, if there is one.
+fn terminal_size() -> Option<(usize, usize)> {
+    let (width, height) = crossterm::terminal::size().ok()?;
+    let mut width = width as usize;
+    if cfg!(windows) {
+        // Windows Terminal has a weird bug. It seems to try to re-wrap text on resize. It does so if the
+        // text goes all the way to the edge of the terminal.  If we leave 1 colum extra, the behavior stops.
+        // 🤦‍♂️
+        // See: https://github.com/microsoft/terminal/issues/3088
+        width = width.saturating_sub(1);
+    }
+    Some((width, height as usize))
+}
+
+/// The direction in which a table's columns are laid out. See: [`Stream::direction`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Left-to-right.
+    Ltr,
+    /// Right-to-left, for languages like Hebrew and Arabic.
+    Rtl,
+}
+
+impl Direction {
+    /// The alignment of text at the start of a line.
+    fn start(self) -> Alignment {
+        match self {
+            Direction::Ltr => Alignment::Left,
+            Direction::Rtl => Alignment::Right,
+        }
+    }
+
+    /// The index of the column to display at `position`.
+    fn index(self, position: usize, num_cols: usize) -> usize {
+        match self {
+            Direction::Ltr => position,
+            Direction::Rtl => num_cols.saturating_sub(position + 1),
+        }
+    }
+}
+
+/// A transformation for column headers. See: [`Stream::header_case`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeaderCase {
+    /// Leave headers as they were given.
+    #[default]
+    AsIs,
+    /// ALL UPPERCASE.
+    Upper,
+    /// Capitalize The First Letter Of Each Word. Other letters are left as-is, to preserve
+    /// acronyms. (ex: `"request URL"` becomes `"Request URL"`)
+    Title,
+}
+
+impl HeaderCase {
+    fn apply(self, header: &str) -> String {
+        match self {
+            HeaderCase::AsIs => header.to_string(),
+            HeaderCase::Upper => header.to_uppercase(),
+            HeaderCase::Title => {
+                let mut title = String::with_capacity(header.len());
+                let mut word_start = true;
+                for c in header.chars() {
+                    if word_start {
+                        title.extend(c.to_uppercase());
+                    } else {
+                        title.push(c);
+                    }
+                    word_start = c.is_whitespace();
+                }
+                title
+            },
+        }
+    }
+}
+
+/// A group of settings for a particular kind of output. See: [`Stream::profile`].
+///
+/// Each profile draws with the plain ASCII [`Theme::default`], never writes colors or other
+/// escape codes, wraps long values instead of truncating them, and buffers every row for an
+/// exact layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Profile {
+    /// Plain text for the body of an email. At most 72 columns wide, which leaves room for
+    /// quoting (`> `) in replies.
+    Email,
+    /// Plain text at most 78 columns wide, the recommended line length for email and
+    /// other text documents.
+    PlainText,
+}
+
+/// An output format for a [`Stream`]. See: [`Stream::format`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Format {
+    /// A table for humans to read, sized to fit the terminal.
+    Table,
+    /// Comma-separated values, with a header record.
+    Csv,
+    /// Tab-separated values, with a header record.
+    /// Tabs, newlines, and backslashes in values are escaped. (ex: `\t`)
+    Tsv,
+    /// An HTML `<table>`, with the title as its caption, and any summary row and footer
+    /// in its `<tfoot>`.
+    Html,
+    /// One JSON object per line, keyed by column headers. Columns without headers are keyed
+    /// by position, and repeated headers are numbered. (ex: `Column 2`, or `Name 2`)
+    /// Values are always strings, exactly as they would be displayed. (ex: `{"Age":"41"}`)
+    JsonLines,
+    /// A LaTeX `tabular` block. Column alignment (`l`, `c`, or `r`) is taken from each
+    /// [`Column`]. The title and footer span all columns.
+    Latex,
+    /// A reStructuredText grid table, ex: for Sphinx docs. Sets [`Stream::borders`] and
+    /// a [`Theme`] to draw the grid, which shouldn't be changed afterwards.
+    Rst,
+}
+
+impl Format {
+    /// Is this format drawn as a table, sized to fit?
+    fn draws_table(self) -> bool {
+        matches!(self, Format::Table | Format::Rst)
+    }
+}
+
+impl FromStr for Format {
+    type Err = ParseFormatError;
+
+    /// Parse a format name, ex: from a `--format` flag.
+    /// (`table`, `csv`, `tsv`, `html`, `jsonl`, `latex`, or `rst`. `json` is accepted for `jsonl`.)
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "table" => Ok(Format::Table),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "html" => Ok(Format::Html),
+            "jsonl" | "json" => Ok(Format::JsonLines),
+            "latex" => Ok(Format::Latex),
+            "rst" => Ok(Format::Rst),
+            _ => Err(ParseFormatError(name.to_string())),
+        }
+    }
+}
+
+/// The error returned when parsing an unknown [`Format`] name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFormatError(String);
+
+impl fmt::Display for ParseFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown format \"{}\" (expected table, csv, tsv, html, jsonl, latex, or rst)", self.0)
+    }
+}
+
+impl std::error::Error for ParseFormatError {}
+
+/// Where to display a pinned row. See: [`Stream::pin`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    /// Just below the headers.
+    Top,
+    /// At the end of the table.
+    Bottom,
+}
+
+/// How to get the user's attention when a table is finished. See: [`Stream::notify_after`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Notification {
+    /// Ring the terminal bell.
+    Bell,
+    /// Ask the terminal to show a desktop notification with this message, via `OSC 9`.
+    /// (Supported by iTerm2, Windows Terminal, and others.)
+    ///
+    /// The message may contain variables. See: [`Stream::title`].
+    Osc9(String),
+}
+
+/// A way for a column to cope when the table is too narrow for its values.
+/// See: [`Column::degrade`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Degrade {
+    /// Wrap values onto multiple lines.
+    Wrap,
+    /// Cut values off at the column's width.
+    Truncate,
+    /// Don't display the column.
+    Hide,
+    /// Widen the column to fit its widest value, even if that makes the table wider than its
+    /// [`max_width`](Stream::max_width). Values that arrive after output starts can't widen
+    /// the rows already displayed, so wider ones are wrapped instead.
+    /// ex: for hashes or IDs that are useless when cut off.
+    Grow,
+    /// Return an error (of kind [`io::ErrorKind::InvalidData`]) when a value doesn't fit,
+    /// rather than cutting it off. The row with that value isn't written, but rows after it
+    /// still are, by later calls.
+    ///
+    /// Rows are buffered until column widths are known, so the error may be returned by a
+    /// later call than the row's own. Its message says which row it was.
+    Error,
+}
+
+/// Which part of a value to cut off, when it's too wide for its column.
+/// See: [`Column::truncate`].
+///
+/// Examples are for `src/lib.rs` in an 8-column-wide column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Truncate {
+    /// Keep the end of the value. (ex: `c/lib.rs`)
+    Start,
+    /// Keep both ends of the value. (ex: `src/b.rs`)
+    Middle,
+    /// Keep the start of the value. (ex: `src/lib.`)
+    #[default]
+    End,
+}
+
+impl Truncate {
+    /// Truncate `value` to at most `width` columns, replacing the part that's cut off
+    /// with `marker`, if there's room for it.
+    /// Any escape codes in the part that's cut off are kept, so that styles are still reset.
+    fn apply<'a>(self, value: &'a str, width: usize, marker: &str) -> Cow<'a, str> {
+        if str_width(value) <= width {
+            return Cow::Borrowed(value);
+        }
+        let marker_width = str_width(marker);
+        let marker = if marker_width < width { marker } else { "" };
+        let room = width - str_width(marker);
+
+        let (head, head_width) = match self {
+            Truncate::Start => ("", 0),
+            Truncate::Middle => width::truncate(value, room - room / 2),
+            Truncate::End => width::truncate(value, room),
+        };
+        let (tail, _) = match self {
+            Truncate::End => ("", 0),
+            _ => width::truncate_start(&value[head.len()..], room - head_width),
+        };
+        let cut = &value[head.len()..value.len() - tail.len()];
+
+        let escapes: String = width::escapes(cut).collect();
+        match (self, escapes.is_empty() && marker.is_empty()) {
+            (Truncate::Start, true) => Cow::Borrowed(tail),
+            (Truncate::End, true) => Cow::Borrowed(head),
+            _ => Cow::Owned(format!("{}{}{}{}", head, marker, escapes, tail)),
+        }
+    }
+}
+
+/// The widths calculated for each column of a table. See: [`Stream::plan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    /// The width of each column's content, not including padding, dividers, or borders.
+    pub widths: Vec<usize>,
+}
+
+/// Information about a table that has been written. See: [`Stream::finish`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Summary {
+    /// The number of rows in the table. (Including any not displayed due to `max_height`.)
+    pub rows: usize,
+
+    /// The time from the first row until the table was finished.
+    pub elapsed: Duration,
+
+    /// The number of cells that were truncated to fit in their columns.
+    pub truncated_cells: usize,
+
+    /// The width of the table as displayed, including any borders.
+    /// Use it with [`Alignment::pad`] to line up other output with the table.
+    /// (0 for machine-readable formats.)
+    pub width: usize,
+
+    /// How wide the table would need to be to display all values without truncation.
+    /// If this is larger than your terminal, you might suggest that users widen it.
+    pub untruncated_width: usize,
+
+    /// Values that failed validation. See: [`Column::validate`].
+    pub validation_errors: Vec<ValidationError>,
+
+    /// Statistics about each column, in the order columns were given. Useful for tuning widths.
+    pub columns: Vec<ColumnStats>,
+}
+
+/// A table rendered to text. See: [`Stream::finish_rendered`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Rendered {
+    /// The full output, including any title, footer, legend, and notes.
+    pub text: String,
+
+    /// The width of the widest line of `text`, in terminal columns.
+    /// (Measured from the text itself, so it includes notes that may be wider than the table.)
+    pub width: usize,
+
+    /// The number of lines in `text`.
+    pub lines: usize,
+
+    /// The same summary as [`Stream::finish`] returns.
+    pub summary: Summary,
+}
+
+/// Statistics about how a column was displayed. See: [`Summary::columns`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ColumnStats {
+    /// The header of the column. (or "Column N" if it has none.)
+    pub name: String,
+
+    /// The width the column was given. (0 if it was hidden.)
+    pub width: usize,
+
+    /// The number of cells in this column that were truncated.
+    pub truncated_cells: usize,
+
+    /// The most columns that were cut off of a single value.
+    /// (ex: If this is 5, `min_width(width + 5)` would have fit every value.)
+    pub max_overflow: usize,
+}
+
+/// A value that failed validation. See: [`Column::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// The (1-based) number of the row, in the order rows were given to the Stream.
+    pub row: usize,
+    /// The header of the column. (or "Column N" if it has none.)
+    pub column: String,
+    /// The message returned by the validator.
+    pub message: String,
+}
+
+/// A function that checks whether a column's value is valid for a row.
+type Validator<T> = dyn Fn(&T) -> Result<(), String>;
+
+/// A function that writes one column's worth of data for a row.
+type Writer<T> = dyn Fn(&mut fmt::Formatter, &T) -> fmt::Result;
 
-            // If we have any left, put it in the biggest column:
-            if remaining_width > 0 {
-                for col in big_cols.iter_mut().rev().take(1) {
-                    col.width += remaining_width;
-                }
-            }
-        }
+/// A function that decides whether two rows should be collapsed together.
+type SameRow<T> = dyn Fn(&T, &T) -> bool;
 
-        true
-    }
+/// Whether to highlight a value. See: [`Column::highlight`].
+type Highlight = dyn Fn(&str) -> bool;
 
-    /// Finish writing output.
-    /// This may write any items still in the buffer,
-    /// as well as a trailing horizontal line and footer.
-    pub fn finish(mut self) -> io::Result<()> {
-        if !self.buffer.is_empty() {
-            self.write_buffer()?;
-        }
-        self.hr()?;
+/// Chooses how to style a cell. See: [`Column::style`].
+type CellStyle<T> = dyn Fn(&T) -> Style;
 
-       
-        Ok(())
-    }
+/// Chooses how to style a row. See: [`Stream::row_style`].
+type RowStyle<T> = dyn Fn(usize, &T) -> Style;
 
-    /// Like [`finish`], but adds a footer at the end as well.
-    pub fn footer(mut self, footer: &str) -> io::Result<()> {
-        if !self.buffer.is_empty() {
-            self.write_buffer()?;
-        }
-        
-        let border_width = if self.borders { 1 } else { 0 } + if self.padding { 1 } else { 0 };
-        let foot_width = self.width - (border_width * 2);
+/// Called when a cell is truncated. See: [`Stream::on_truncate`].
+type OnTruncate = dyn FnMut(usize, usize);
 
-        self.hr()?;
-        self.border_left()?;
-        Alignment::Center.write(&mut self.output, foot_width, &footer)?;
-        self.border_right()?;
-        self.hr()?;
+/// The chapter that a row belongs in. See: [`Stream::chapter_by`].
+type ChapterKey<T> = dyn Fn(&T) -> String;
 
-        Ok(())
-    }
-}
+/// A number from a row. See: [`Stream::percent_of_total`], [`Stream::rollup`].
+type RowValue<T> = dyn Fn(&T) -> f64;
+
+/// The total of a [`RowValue`] over the rows received so far.
+type RunningTotal<T> = (Box<RowValue<T>>, Rc<Cell<f64>>);
+
+/// A typed column's value as a number, if it is one. See: [`CellFormat::number`].
+type RowNumber<T> = dyn Fn(&T) -> Option<f64>;
+
+/// Writes a total of a typed column's numbers. See: [`CellFormat::write_number`].
+type NumberWriter = dyn Fn(&mut fmt::Formatter, f64) -> fmt::Result;
+
+/// A yes-or-no value from a row. See: [`Column::boolean`].
+type RowFlag<T> = dyn Fn(&T) -> bool;
+
+/// How old a row's data is. See: [`Stream::style_by_age`].
+type RowAge<T> = dyn Fn(&T) -> Duration;
 
 /// Configure how we want to display a single column.
 pub struct Column<T> {
     header: Option<String>,
-    writer: Box<dyn Fn(&mut fmt::Formatter, &T) -> fmt::Result>,
+    writer: Box<Writer<T>>,
+    memo: Option<Box<dyn Memo<T>>>,
+    counter: Option<Counter>, // displayed instead of the writer's value.
+
+    alignment: Option<Alignment>, // None: align to the start of the line.
+
+    elide_prefix: bool,
+    prefix: Option<String>, // calculated common prefix to elide.
 
-    alignment: Alignment,
+    fill: char,
+    hidden: bool,
+    auto_hidden: bool, // by the layout, ex: to fit. Reset each time widths are calculated.
+    other_format: bool, // hidden, since it's only_for() other formats than the Stream's.
+    formats: Option<Vec<Format>>, // None: all formats.
+    degrade: Option<Vec<Degrade>>, // None: use the Stream's default.
 
-    // Min size specified by user
+    validator: Option<Box<Validator<T>>>,
+    style: Option<Box<CellStyle<T>>>,
+    highlights: Vec<(Box<Highlight>, Style)>,
+
+    // Min and max sizes specified by user
     min_width: usize,
+    width_limit: Option<usize>,
+    width_pct: Option<f32>,
+    priority: Option<u32>, // None: only hidden if degrade() says so, at priority 0.
+    keep_position: bool,
+    truncate: Truncate,
+    hide_if_empty: bool,
+    none_as: Option<String>, // placeholder for empty values.
+    boolean: Option<(Rc<RowFlag<T>>, BoolStyle)>, // displayed in tables instead of the writer's value.
+    footer: Option<FooterCell>,
+    numbers: Option<(Box<RowNumber<T>>, Box<NumberWriter>)>, // from Column::typed, for footer totals.
+    flex: usize, // share of extra width, when growing.
 
     // calculated size.
     width: usize,
+    target_width: Option<usize>, // from width_pct, for the current layout.
 
     // Temp vars used while calculating the width:
 
     max_width: usize, // max size encountered in buffer data.
     width_sum: usize, // sum of widths of all rows. Used to weigh column widths.
 
+    widest: usize, // max size of all data displayed.
+    values: usize, // how many rows have had a non-empty value?
+    has_text: bool, // has any row had a value that isn't a number?
+    shrunk: bool, // to its header's width, since it's sparse. See: Stream::shrink_sparse_columns()
+    truncated_cells: usize,
+    max_overflow: usize, // the most columns cut off of a truncated value.
+    longest_word: usize, // Only measured for columns that may wrap.
+
     _pd: PhantomData<T>,
 }
 
@@ -514,26 +3075,144 @@ impl <T> Column<T> {
         Self {
             header: None,
             writer: Box::new(func),
-            alignment: Alignment::Left,
+            memo: None,
+            counter: None,
+            alignment: None,
+            elide_prefix: false,
+            prefix: None,
+            fill: ' ',
+            hidden: false,
+            auto_hidden: false,
+            other_format: false,
+            formats: None,
+            degrade: None,
+            validator: None,
+            style: None,
+            highlights: vec![],
 
             // a min-width of 1 means we'll always at least show there was *some* data in a col,
             // even if it's truncated.
             min_width: 1,
+            width_limit: None,
+            width_pct: None,
+            priority: None,
+            keep_position: false,
+            truncate: Truncate::End,
+            hide_if_empty: false,
+            none_as: None,
+            boolean: None,
+            footer: None,
+            numbers: None,
+            values: 0,
+            shrunk: false,
+            has_text: false,
+            truncated_cells: 0,
+            max_overflow: 0,
+            target_width: None,
+            flex: 1,
             width: 0,
             max_width: 0,
             width_sum: 0,
-
+            widest: 0,
+            longest_word: 0,
 
             _pd: Default::default(),
         }
     }
 
+    /// Create a new Column which extracts a value from T with `value`, and displays it with `format`.
+    ///
+    /// ```
+    /// # use tablestream::*;
+    /// struct City { population: f64 }
+    /// let col = Column::typed(|c: &City| c.population, NumberFormat::default().precision(1));
+    /// ```
+    ///
+    /// Numeric formats are right-aligned by default.
+    pub fn typed<V, A, F>(value: A, format: F) -> Self
+    where
+        A: Fn(&T) -> V + 'static,
+        F: CellFormat<V> + 'static,
+    {
+        let alignment = if format.is_numeric() { Some(Alignment::Right) } else { None };
+        let (value, format) = (Rc::new(value), Rc::new(format));
+        let (number_of, number_format) = (Rc::clone(&value), Rc::clone(&format));
+        let total_format = Rc::clone(&format);
+        let mut col = Self::new(move |f, row| format.write(f, &value(row)));
+        col.alignment = alignment;
+        col.numbers = Some((
+            Box::new(move |row| number_format.number(&number_of(row))),
+            Box::new(move |f, total| total_format.write_number(f, total)),
+        ));
+        col
+    }
+
+    /// Create a new Column which displays a date or time from the [`chrono`] crate with a
+    /// `strftime`-style `pattern`. (Requires the `chrono` feature.)
+    ///
+    /// ```
+    /// # use tablestream::*;
+    /// struct Event { at: chrono::DateTime<chrono::Utc> }
+    /// let col = Column::datetime(|e: &Event| e.at, "%Y-%m-%d %H:%M").header("Time");
+    /// ```
+    ///
+    /// The column's min_width is set to the widest value the pattern can display, so that its
+    /// width doesn't depend on which rows were buffered. See: [`DateTimeFormat`].
+    #[cfg(feature = "chrono")]
+    pub fn datetime<V, A>(value: A, pattern: &str) -> Self
+    where
+        A: Fn(&T) -> V + 'static,
+        DateTimeFormat: CellFormat<V>,
+    {
+        let format = DateTimeFormat::new(pattern);
+        let width = format.width();
+        Self::typed(value, format).min_width(width)
+    }
+
+    /// Create a new Column which displays a `bool` extracted from T with `value`, in `style`.
+    /// (ex: `✓` and `✗`)
+    ///
+    /// ```
+    /// # use tablestream::*;
+    /// struct Feature { name: String, enabled: bool }
+    /// let col = Column::boolean(|f: &Feature| f.enabled, BoolStyle::CheckCross).header("Enabled");
+    /// ```
+    ///
+    /// Machine-readable formats keep `true` and `false`.
+    /// (Use [`Column::typed`] with a [`BoolStyle`] to display `style` in them too.)
+    pub fn boolean<A>(value: A, style: BoolStyle) -> Self
+    where
+        A: Fn(&T) -> bool + 'static,
+    {
+        let value = Rc::new(value);
+        let flag = Rc::clone(&value);
+        let mut col = Self::new(move |f, row| write!(f, "{}", flag(row)));
+        col.boolean = Some((value, style));
+        col
+    }
+
+    /// Create a new Column which displays a preview of binary data extracted from T with
+    /// `bytes`. (ex: `de ad be ef …`)
+    ///
+    /// ```
+    /// # use tablestream::*;
+    /// struct Packet { payload: Vec<u8> }
+    /// let col = Column::hex(|p: &Packet| &p.payload, HexFormat::default().group(2));
+    /// ```
+    ///
+    /// Unlike [`Column::typed`], `bytes` can borrow from the row, so that it needn't be copied.
+    pub fn hex<A>(bytes: A, format: HexFormat) -> Self
+    where A: Fn(&T) -> &[u8] + 'static
+    {
+        Self::new(move |f, row| format.write(f, &bytes(row)))
+    }
+
     /// Set a column header.
     ///
     /// Note: This will increase the min_width of your column to the size of the header.
     pub fn header(mut self, name: &str) -> Self {
         self.header = Some(name.to_string());
-        self.min_width = max(self.min_width, name.len());
+        self.min_width = max(self.min_width, str_width(name));
         self
     }
 
@@ -546,66 +3225,624 @@ impl <T> Column<T> {
         self
     }
 
-    /// Align left. (This is the default.)
+    /// Set the maximum width of the column. Longer values will be truncated or wrapped.
+    ///
+    /// Useful for verbose columns (ex: log messages), so that the space they would have used
+    /// goes to other columns instead. The column won't grow past this width, even if the
+    /// Stream [grows](Stream::grow) to fill its max_width.
+    ///
+    /// Note: The column's min_width (including the width of its header) takes precedence.
+    pub fn max_width(mut self, max_width: usize) -> Self {
+        self.width_limit = Some(max_width);
+        self
+    }
+
+    /// How much of any extra width this column gets, relative to other columns, when the
+    /// Stream [grows](Stream::grow) to fill its max_width. (default: 1)
+    ///
+    /// ex: A column with `flex(3)` grows 3x as much as a column with the default.
+    /// `flex(0)` keeps a column at its natural width.
+    pub fn flex(mut self, weight: usize) -> Self {
+        self.flex = weight;
+        self
+    }
+
+    /// Make this column `pct` percent of the table's available width. (That is, of its
+    /// max_width, not counting borders and dividers.) Other columns share the rest.
+    ///
+    /// Unlike widths calculated from values, this keeps layouts predictable across terminals
+    /// of different sizes. Note: The column's min_width still takes precedence.
+    pub fn width_pct(mut self, pct: f32) -> Self {
+        self.width_pct = Some(pct.clamp(0.0, 100.0));
+        self
+    }
+
+    /// Always make this column exactly `width` columns wide, regardless of its values.
+    ///
+    /// Useful with [`Stream::no_buffer`], since the layout doesn't depend on which rows have
+    /// been seen so far. Note: Setting a [`header`](Column::header) afterwards will widen the
+    /// column to fit it.
+    pub fn fixed_width(self, width: usize) -> Self {
+        self.min_width(width).max_width(width)
+    }
+
+    /// Cache this column's rendered values, keyed by `key`.
+    ///
+    /// Useful for columns that display the same small set of values over and over (enums,
+    /// country names, ...), since each distinct value is only formatted and measured once.
+    /// Note: The cache is never evicted, so don't use this for columns with many distinct values.
+    pub fn memoize<K, F>(mut self, key: F) -> Self
+    where
+        K: Hash + Eq + 'static,
+        F: Fn(&T) -> K + 'static,
+    {
+        self.memo = Some(Box::new(KeyedMemo{ key, cache: HashMap::new() }));
+        self
+    }
+
+    /// Hide any prefix that is common to all values in the column. (ex: `/home/user/project/`)
+    ///
+    /// The prefix is detected from the rows that are buffered before output starts, and is
+    /// shown once in a note below the table instead of in every cell.
+    /// Prefixes only end after a non-alphanumeric character, so words aren't split.
+    pub fn elide_prefix(mut self) -> Self {
+        self.elide_prefix = true;
+        self
+    }
+
+    /// The character used to pad values to the width of the column. (default: `' '`)
+    ///
+    /// Padding is applied between the value and the opposite edge, according to the
+    /// column's alignment. ex: Leader dots with `.fill('.')`: `Name ........`
+    pub fn fill(mut self, fill: char) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    /// Don't display this column in the table.
+    ///
+    /// Hidden columns aren't rendered in the table, but are otherwise treated like any other
+    /// column, so that one list of columns can serve both pretty and machine-readable output.
+    pub fn hidden(mut self) -> Self {
+        self.hidden = true;
+        self
+    }
+
+    /// Only include this column in output of the given [`Format`].
+    ///
+    /// Call more than once to include the column in several formats.
+    /// ex: Show a raw byte count only in CSV, and a human-readable size only in the table.
+    pub fn only_for(mut self, format: Format) -> Self {
+        self.formats.get_or_insert_with(Vec::new).push(format);
+        self
+    }
+
+    /// Hide this column when the table is too narrow for it, before hiding any columns with
+    /// a higher priority.
+    ///
+    /// When the table can't fit every column at its [`min_width`](Column::min_width) (which
+    /// includes its header), columns with a priority are dropped, lowest first, instead of
+    /// squeezing them all. Ties are broken by dropping the rightmost column first.
+    /// (Columns hidden via [`Degrade::Hide`] without a priority have priority 0.)
+    ///
+    /// See also: [`Stream::note_dropped_columns`].
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Hide this column if all of its values are empty in the rows buffered before output
+    /// starts. Handy for optional fields, which are often unset.
+    pub fn hide_if_empty(mut self) -> Self {
+        self.hide_if_empty = true;
+        self
+    }
+
+    /// Display `placeholder` in place of empty values. (ex: `"-"` for `None`)
+    ///
+    /// ```
+    /// # use tablestream::*;
+    /// struct User { name: String, nickname: Option<String> }
+    /// let col = col!(User: .nickname?).header("Nickname").none_as("-");
+    /// ```
+    ///
+    /// Placeholders are only displayed in tables. Machine-readable formats leave empty values
+    /// empty. Empty values still count as empty for [`Column::hide_if_empty`].
+    /// See also: [`Stream::none_as`], to set a placeholder for every column.
+    pub fn none_as(mut self, placeholder: &str) -> Self {
+        self.none_as = Some(width::printable(placeholder).into_owned());
+        self
+    }
+
+    /// Display `text` in this column's cell of the footer row. (ex: `"Total"`)
+    /// See: [`Stream::footer_row`].
+    pub fn footer_text(mut self, text: &str) -> Self {
+        self.footer = Some(FooterCell::Text(width::printable(text).into_owned()));
+        self
+    }
+
+    /// Display the number of rows in this column's cell of the footer row. (ex: `42 rows`)
+    /// See: [`Stream::footer_row`].
+    /// See also: [`Stream::count_rows`], to count rows on a line of their own.
+    pub fn footer_count(mut self) -> Self {
+        self.footer = Some(FooterCell::Count(0));
+        self
+    }
+
+    /// Display the sum of this column's values in the footer row. See: [`Stream::footer_row`].
+    ///
+    /// Only columns created with [`Column::typed`] have sums, of the values that their format
+    /// counts as numbers. (See: [`CellFormat::number`]) Values are summed before they're
+    /// rounded, and the sum is displayed in the column's format. (ex: `1.5 GiB` with a
+    /// [`ByteFormat`]) Other columns' cells are left empty.
+    pub fn footer_sum(mut self) -> Self {
+        self.footer = Some(FooterCell::Sum(Aggregate::default()));
+        self
+    }
+
+    /// Display the average of this column's values in the footer row.
+    /// Like [`Column::footer_sum`], only typed columns have averages, of values that are numbers.
+    pub fn footer_avg(mut self) -> Self {
+        self.footer = Some(FooterCell::Avg(Aggregate::default()));
+        self
+    }
+
+    /// Which part of values to cut off when they're too wide for this column.
+    /// (default: [`Truncate::End`])
+    ///
+    /// ex: `Truncate::Start` for file paths, so that file names stay visible.
+    pub fn truncate(mut self, truncate: Truncate) -> Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Keep this column at its position, even if the Stream
+    /// [orders columns by width](Stream::order_by_width).
+    pub fn keep_position(mut self) -> Self {
+        self.keep_position = true;
+        self
+    }
+
+    /// What to do when this column is too narrow for its values.
+    /// (default: `[Degrade::Truncate]`, or `[Degrade::Wrap]` with [`Stream::wrap`])
+    ///
+    /// Steps are taken in order, as the table gets narrower:
+    /// * [`Degrade::Wrap`] applies until the column is narrower than its longest word.
+    /// * [`Degrade::Truncate`] applies down to the column's [`min_width`](Column::min_width).
+    /// * [`Degrade::Hide`] hides the column rather than squeezing it below its `min_width`.
+    /// * [`Degrade::Grow`] keeps the column wide enough for its values, so later steps never apply.
+    /// * [`Degrade::Error`] fails to write rows with values that don't fit.
+    ///
+    /// If there's no later step, `Wrap` also breaks words that are too long for the column.
+    ///
+    /// ex: `.min_width(10).degrade(&[Degrade::Wrap, Degrade::Truncate, Degrade::Hide])`
+    pub fn degrade(mut self, steps: &[Degrade]) -> Self {
+        self.degrade = Some(steps.to_vec());
+        self
+    }
+
+    /// Check each row's value for this column.
+    ///
+    /// Invalid cells are marked with a `!`, and the errors are listed below the table
+    /// and returned in the [`Summary`].
+    ///
+    /// Every row is validated, including rows that aren't displayed (ex: hidden by
+    /// [`Stream::max_height`]), and errors refer to rows by the order they were given to the
+    /// Stream. `validator` may be called more than once for each row.
+    pub fn validate<F>(mut self, validator: F) -> Self
+    where F: Fn(&T) -> Result<(), String> + 'static
+    {
+        self.validator = Some(Box::new(validator));
+        self
+    }
+
+    /// Choose a [`Style`] for each row's cell in this column.
+    ///
+    /// ```
+    /// # use tablestream::*;
+    /// struct Person { name: String, age: u8 }
+    ///
+    /// let col = col!(Person: .age).style(|p: &Person| {
+    ///     if p.age > 90 { Style::red() } else { Style::default() }
+    /// });
+    /// ```
+    ///
+    /// The style covers the whole padded cell, and is combined with any row style, ex: from
+    /// [`Stream::style_by_age`]. Colors set for the cell take precedence.
+    pub fn style<F>(mut self, style: F) -> Self
+    where F: Fn(&T) -> Style + 'static
+    {
+        self.style = Some(Box::new(style));
+        self
+    }
+
+    /// Style cells whose displayed value matches `condition`.
+    ///
+    /// ```
+    /// # use tablestream::*;
+    /// struct Account { balance: i64 }
+    ///
+    /// let col = col!(Account: .balance)
+    ///     .highlight(|value| value.parse::<i64>().is_ok_and(|v| v < 0), Style::red())
+    ///     .highlight(|value| value == "0", Style::default().dim());
+    /// ```
+    ///
+    /// If several conditions match, they're all applied, and earlier ones take precedence.
+    /// Highlights take precedence over [`Column::style`] and row styles.
+    pub fn highlight<F>(mut self, condition: F, style: Style) -> Self
+    where F: Fn(&str) -> bool + 'static
+    {
+        self.highlights.push((Box::new(condition), style));
+        self
+    }
+
+    /// The combined style of all highlights that match `value`.
+    fn highlight_for(&self, value: &str) -> Style {
+        self.highlights.iter().rev()
+            .filter(|(condition, _)| condition(value))
+            .fold(Style::default(), |style, (_, highlight)| highlight.or(style))
+    }
+
+    /// Align left. (This is the default for left-to-right tables.)
+    ///
+    /// Columns without an alignment whose buffered values are all numbers (ex: `42`, `-3.5`,
+    /// `1,024`, or `50%`) are aligned right instead, so that their digits line up.
     pub fn left(mut self) -> Self {
-        self.alignment = Alignment::Left;
+        self.alignment = Some(Alignment::Left);
         self
     }
 
     /// Align right.
     pub fn right(mut self) -> Self {
-        self.alignment = Alignment::Right;
+        self.alignment = Some(Alignment::Right);
         self
     }
 
     /// Center-align.
     pub fn center(mut self) -> Self {
-        self.alignment = Alignment::Center;
+        self.alignment = Some(Alignment::Center);
         self
     }
 }
 
-enum Alignment {
+impl <T> Column<T> {
+    /// A column that displays a [`Counter`] for each row, for columns the Stream adds itself.
+    fn counter(counter: Counter) -> Self {
+        let mut col = Column::new(|_, _| Ok(()));
+        col.counter = Some(counter);
+        col
+    }
+
+    /// Render this column's value for `row` into `buf`. Returns the display width of the value.
+    fn render(&mut self, row: &T, info: RowInfo, buf: &mut String) -> Result<usize, fmt::Error> {
+        if let Some(counter) = self.counter {
+            counter.write(info, buf)?;
+            return Ok(str_width(buf));
+        }
+        if let Some((value, style)) = &self.boolean {
+            buf.push_str(style.text(value(row)));
+            return Ok(str_width(buf));
+        }
+
+        let writer = self.writer.as_ref();
+        let mut width = match self.memo.as_mut() {
+            Some(memo) => memo.render(row, writer, buf)?,
+            None => {
+                write!(buf, "{}", Displayer{ row, writer })?;
+                if let Cow::Owned(value) = width::printable(buf) {
+                    *buf = value;
+                }
+                str_width(buf)
+            }
+        };
+
+        if let Some(prefix) = &self.prefix {
+            if buf.starts_with(prefix.as_str()) {
+                buf.drain(..prefix.len());
+                width = width.saturating_sub(str_width(prefix));
+            }
+        }
+
+        Ok(width)
+    }
+
+    /// Should this column be included in output of `format`?
+    /// (Hidden columns are only hidden from tables.)
+    fn shown_in(&self, format: Format) -> bool {
+        self.formats.as_ref().is_none_or(|formats| formats.contains(&format))
+    }
+
+    /// This column's share of extra width. See: [`Column::flex`].
+    fn flex_weight(&self) -> usize {
+        if self.shrunk || self.target_width.or(self.width_limit).is_some() { 0 } else { self.flex }
+    }
+
+    /// `width`, limited to the column's [`max_width`](Column::max_width) or
+    /// [`width_pct`](Column::width_pct), if any.
+    fn limit(&self, width: usize) -> usize {
+        self.target_width.or(self.width_limit).map_or(width, |limit| min(width, limit))
+    }
+
+    /// The narrowest this column can be, in the current layout.
+    fn min(&self) -> usize {
+        let min_width = max(self.min_width, self.target_width.unwrap_or(0));
+        match self.steps(false).contains(&Degrade::Grow) {
+            true => max(min_width, self.max_width),
+            false => min_width,
+        }
+    }
+
+    /// Hide this column from tables drawn in `format`, if it's [only for](Column::only_for)
+    /// other formats.
+    fn use_format(&mut self, format: Format) {
+        self.other_format = !self.shown_in(format);
+    }
+
+    /// Is this column displayed in the table, in the current layout?
+    fn visible(&self) -> bool {
+        !self.hidden && !self.auto_hidden && !self.other_format
+    }
+
+    /// Does this column's validator reject `row`?
+    fn invalid(&self, row: &T) -> bool {
+        self.validator.as_ref().is_some_and(|validator| validator(row).is_err())
+    }
+
+    /// Render this column's footer cell into `buf`. Returns its display width.
+    fn render_footer(&self, buf: &mut String) -> usize {
+        if let Some(footer) = &self.footer {
+            buf.push_str(&footer.text(self.number_writer()));
+        }
+        str_width(buf)
+    }
+
+    /// How to write totals of this column's values, if it has numbers. See: [`Column::typed`].
+    fn number_writer(&self) -> Option<&NumberWriter> {
+        self.numbers.as_ref().map(|(_, writer)| writer.as_ref())
+    }
+
+    /// If `buf` is empty, replace it with this column's placeholder, if any.
+    /// Returns the width of the value in `buf`, given its current `width`.
+    fn fill_empty(&self, buf: &mut String, width: usize) -> usize {
+        match &self.none_as {
+            Some(placeholder) if buf.is_empty() => {
+                buf.push_str(placeholder);
+                str_width(placeholder)
+            },
+            _ => width,
+        }
+    }
+
+    fn apply_header_case(&mut self, case: HeaderCase) {
+        let Some(header) = &self.header else { return };
+        let header = case.apply(header);
+        self.min_width = max(self.min_width, str_width(&header));
+        self.header = Some(header);
+    }
+
+    /// The steps from [`Column::degrade`], or the Stream's default.
+    fn steps(&self, wrap: bool) -> &[Degrade] {
+        match &self.degrade {
+            Some(steps) => steps,
+            None if wrap => &[Degrade::Wrap],
+            None => &[Degrade::Truncate],
+        }
+    }
+
+    /// How values wider than this column are displayed, at its current width.
+    fn overflow(&self, wrap: bool) -> Degrade {
+        let steps = self.steps(wrap);
+        for (i, step) in steps.iter().enumerate() {
+            let last = steps[i + 1..].iter().all(|s| *s == Degrade::Hide);
+            match step {
+                Degrade::Wrap if last || self.width >= self.longest_word => return Degrade::Wrap,
+                Degrade::Truncate => return Degrade::Truncate,
+                // Values too wide for a column that grew arrived late. Don't lose them:
+                Degrade::Grow => return Degrade::Wrap,
+                Degrade::Error => return Degrade::Error,
+                _ => {},
+            }
+        }
+        Degrade::Truncate
+    }
+
+    /// Can this column be hidden when there isn't room for it?
+    fn can_hide(&self) -> bool {
+        self.priority.is_some() || self.steps(false).contains(&Degrade::Hide)
+    }
+
+    /// Should we measure the longest word in this column?
+    fn may_wrap(&self, wrap: bool) -> bool {
+        self.steps(wrap).contains(&Degrade::Wrap)
+    }
+
+    /// A name to refer to this column by in notes.
+    fn name(&self, index: usize) -> String {
+        match &self.header {
+            Some(header) => header.clone(),
+            None => format!("Column {}", index + 1),
+        }
+    }
+}
+
+/// A number that the Stream keeps for each row, displayed by columns it adds itself.
+#[derive(Clone, Copy)]
+enum Counter {
+    Repeats, // See: Stream::collapse_duplicates()
+    Number, // See: Stream::number_rows()
+}
+
+impl Counter {
+    fn write(self, info: RowInfo, buf: &mut String) -> fmt::Result {
+        match self {
+            Counter::Repeats if info.repeats > 1 => write!(buf, "×{}", info.repeats),
+            Counter::Number if info.number > 0 => write!(buf, "{}", info.number),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// What the Stream knows about a row it's rendering. (Defaults for rows that aren't data
+/// rows, like pinned and summary rows.)
+#[derive(Clone, Copy, Default)]
+struct RowInfo {
+    repeats: usize, // how many times the row was repeated. See: Stream::collapse_duplicates()
+    number: usize, // See: Stream::number_rows()
+    validate: bool, // mark invalid values? See: Column::validate()
+}
+
+impl RowInfo {
+    fn data(repeats: usize, number: usize) -> Self {
+        Self{ repeats, number, validate: true }
+    }
+}
+
+/// A cache of rendered column values.
+trait Memo<T> {
+    /// Like [`Column::render`], but may use a cached value instead of calling `writer`.
+    fn render(&mut self, row: &T, writer: &Writer<T>, buf: &mut String) -> Result<usize, fmt::Error>;
+}
+
+struct KeyedMemo<K, F> {
+    key: F,
+    cache: HashMap<K, (String, usize)>,
+}
+
+impl <T, K, F> Memo<T> for KeyedMemo<K, F>
+where
+    K: Hash + Eq,
+    F: Fn(&T) -> K,
+{
+    fn render(&mut self, row: &T, writer: &Writer<T>, buf: &mut String) -> Result<usize, fmt::Error> {
+        let (value, width) = match self.cache.entry((self.key)(row)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = Displayer{ row, writer }.to_string();
+                let value = width::printable(&value).into_owned();
+                let width = str_width(&value);
+                entry.insert((value, width))
+            }
+        };
+        buf.push_str(value);
+        Ok(*width)
+    }
+}
+
+/// How to align text within a fixed width.
+///
+/// Handy for lining up other output (ex: a legend or status line) with a table:
+///
+/// ```
+/// # use tablestream::Alignment;
+/// assert_eq!(Alignment::Right.pad("42", 5), "   42");
+/// assert_eq!(Alignment::Center.pad("日本", 6), " 日本 ");
+/// assert_eq!(Alignment::Left.pad("truncated", 5), "trunc");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    /// Align to the left, padding on the right.
     Left,
+    /// Center, with any odd column of padding on the right.
     Center,
+    /// Align to the right, padding on the left.
     Right,
 }
 
 impl Alignment {
-    // Write into a column of some width.
-    // Truncates to be no more than that size. 
-    // pads to be exactly that size.
-    fn write<W: io::Write>(&self, out: &mut W, col_width: usize, value: &str) -> io::Result<()> {
-        let (value, width) = value.unicode_truncate(col_width);
+    /// Pad `value` to exactly `width` terminal columns, truncating it if it's too wide.
+    ///
+    /// Like table cells, this accounts for wide characters (ex: CJK and emoji), replaces
+    /// control characters, and ignores ANSI color codes.
+    pub fn pad(self, value: &str, width: usize) -> String {
+        let mut out = Vec::with_capacity(width);
+        // Writing to a Vec can't fail, and we only write valid UTF-8:
+        self.write(&mut out, width, value).expect("write to a Vec");
+        String::from_utf8(out).expect("valid UTF-8")
+    }
+
+    /// Like [`pad`](Alignment::pad), but writes to `out`.
+    /// Returns the width written, which is always `width`.
+    pub fn write<W: io::Write>(&self, out: &mut W, width: usize, value: &str) -> io::Result<usize> {
+        self.write_filled(out, width, value, ' ')
+    }
+
+    // Like write(), but pads with `fill`. A space is still left next to the value, so that it
+    // doesn't run into the fill. (ex: `Name .......`)
+    fn write_filled<W: io::Write>(&self, out: &mut W, col_width: usize, value: &str, fill: char) -> io::Result<usize> {
+        self.write_styled(out, col_width, value, fill, Style::default(), true)
+    }
+
+    // Like write_filled(), but in `style`. If `whole_cell`, padding is styled too, so that
+    // backgrounds form solid blocks. Otherwise, only the value is.
+    fn write_styled<W: io::Write>(
+        &self,
+        out: &mut W,
+        col_width: usize,
+        value: &str,
+        fill: char,
+        style: Style,
+        whole_cell: bool,
+    ) -> io::Result<usize> {
+        let value = width::printable(value);
+        let value = Truncate::End.apply(&value, col_width, "");
+        let width = str_width(&value);
+        let padding = col_width.saturating_sub(width);
         let (lpad, rpad) = match self {
-            Alignment::Left => (0, col_width - width),
-            Alignment::Right => (col_width - width, 0),
+            Alignment::Left => (0, padding),
+            Alignment::Right => (padding, 0),
             Alignment::Center => {
-                let padding = col_width - width;
                 let half = padding / 2;
                 let remainder = padding % 2;
                 (half, half + remainder)
             }
         };
+        if whole_cell {
+            style.write_start(out)?;
+        }
         // Note: We don't use Rust's built-in width formatter because
         // it just counts chars. Do our own padding:
-        write!(out, "{0:1$}{3}{0:2$}", "", lpad, rpad, value)
+        if fill == ' ' && (whole_cell || style.is_plain()) {
+            write!(out, "{0:1$}{3}{0:2$}", "", lpad, rpad, value)?;
+        } else {
+            let (left, right) = if fill == ' ' {
+                (" ".repeat(lpad), " ".repeat(rpad))
+            } else {
+                let gap = if value.is_empty() { 0 } else { 1 };
+                let filled = |n: usize| std::iter::repeat_n(fill, n.saturating_sub(gap)).collect::<String>();
+                let left = if lpad > 0 { format!("{}{:2$}", filled(lpad), "", gap) } else { String::new() };
+                let right = if rpad > 0 { format!("{:2$}{}", "", filled(rpad), gap) } else { String::new() };
+                (left, right)
+            };
+            write!(out, "{}", left)?;
+            if !whole_cell {
+                style.write_start(out)?;
+            }
+            write!(out, "{}", value)?;
+            if !whole_cell {
+                style.write_end(out)?;
+            }
+            write!(out, "{}", right)?;
+        }
+        if whole_cell {
+            style.write_end(out)?;
+        }
+        Ok(lpad + width + rpad)
     }
 }
 
-trait ToIOResult {
-    fn to_io(self) -> io::Result<()>;
+trait ToIOResult<T> {
+    fn to_io(self) -> io::Result<T>;
 }
 
-impl ToIOResult for fmt::Result {
-    fn to_io(self) -> io::Result<()> {
-        self.map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+impl <T> ToIOResult<T> for Result<T, fmt::Error> {
+    fn to_io(self) -> io::Result<T> {
+        self.map_err(io::Error::other)
     }
 }
 
 struct Displayer<'a, T> {
     row: &'a T,
-    writer: &'a dyn Fn(&mut fmt::Formatter, &T) -> fmt::Result,
+    writer: &'a Writer<T>,
 }
 
 impl <'a, T> fmt::Display for Displayer<'a, T> {
@@ -617,12 +3854,19 @@ impl <'a, T> fmt::Display for Displayer<'a, T> {
 /// Create a new column. Saves some boilerplate vs. `Column::new(...)`.
 ///
 /// See top-level docs for examples.
+/// Use `.field?` for `Option` fields, which display nothing for `None`. (See: [`Column::none_as`])
 // I wish I could use column!(), but that's already taken by Rust. 🤦‍♂️
 #[macro_export]
 macro_rules! col {
     ($t:ty : .$field:ident) => {
         $crate::Column::new(|f, row: &$t| write!(f, "{}", row.$field))
     };
+    ($t:ty : .$field:ident ?) => {
+        $crate::Column::new(|f, row: &$t| match &row.$field {
+            Some(value) => write!(f, "{}", value),
+            None => Ok(()),
+        })
+    };
     ($t:ty : $s:literal, $(.$field:ident),*) => {
         $crate::Column::new(|f, row: &$t| write!(f, $s, $(row.$field)*,))
     };
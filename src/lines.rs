@@ -0,0 +1,260 @@
+//! Re-tabling line-oriented text, ex: the output of other tools.
+
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    process::{Command, ExitStatus, Stdio},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+};
+
+use crate::{Column, IDLE_FLUSH, Stream, Summary};
+
+/// A row of fields, split from one line of text.
+pub type Fields = Vec<String>;
+
+/// How to split a line of text into fields. See: [`LineStream`].
+pub enum Split {
+    /// Split on runs of whitespace, like `awk`. Leading and trailing whitespace is ignored.
+    Whitespace,
+    /// Split on each occurrence of a delimiter. (ex: `","` or `"\t"`)
+    Delimiter(String),
+    /// Split with your own function.
+    With(Box<dyn Fn(&str) -> Fields>),
+}
+
+impl Split {
+    /// Split with your own function, ex: a regular expression.
+    pub fn with<F: Fn(&str) -> Fields + 'static>(split: F) -> Self {
+        Split::With(Box::new(split))
+    }
+
+    /// Split `line` into at most `max_fields`. (The last field gets the rest of the line.)
+    fn split(&self, line: &str, max_fields: usize) -> Fields {
+        match self {
+            Split::Whitespace => {
+                let mut fields = vec![];
+                let mut rest = line.trim();
+                while !rest.is_empty() {
+                    if fields.len() + 1 == max_fields {
+                        fields.push(rest.to_string());
+                        break;
+                    }
+                    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                    fields.push(rest[..end].to_string());
+                    rest = rest[end..].trim_start();
+                }
+                fields
+            },
+            Split::Delimiter(delimiter) => line.splitn(max_fields, delimiter.as_str()).map(String::from).collect(),
+            Split::With(split) => {
+                let mut fields = split(line);
+                if fields.len() > max_fields {
+                    let rest = fields.split_off(max_fields - 1).join(" ");
+                    fields.push(rest);
+                }
+                fields
+            },
+        }
+    }
+}
+
+impl fmt::Debug for Split {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Split::Whitespace => write!(f, "Whitespace"),
+            Split::Delimiter(delimiter) => f.debug_tuple("Delimiter").field(delimiter).finish(),
+            Split::With(_) => write!(f, "With(..)"),
+        }
+    }
+}
+
+/// Displays line-oriented text as a table. Write text to it (ex: from a child process), and
+/// each line becomes a row.
+///
+/// Columns are added as needed to fit the fields of each line.
+///
+/// ```
+/// # use std::io::{self, Write};
+/// # use tablestream::*;
+/// let mut table = LineStream::new(io::stdout(), Split::Whitespace).header_line(true);
+/// table.write_all(b"PID COMMAND\n1 init\n42 sh\n")?;
+/// let summary = table.finish()?;
+/// assert_eq!(2, summary.rows);
+/// # Ok::<(), io::Error>(())
+/// ```
+pub struct LineStream<Out: Write> {
+    stream: Stream<Fields, Out>,
+    split: Split,
+    header_line: bool,
+    headers: Vec<String>,
+    max_fields: usize,
+    num_cols: usize,
+    partial: Vec<u8>, // an incomplete line.
+}
+
+impl <Out: Write> LineStream<Out> {
+    /// Create a table that splits lines into fields with `split`.
+    pub fn new(output: Out, split: Split) -> Self {
+        Self {
+            stream: Stream::new(output, vec![]),
+            split,
+            header_line: false,
+            headers: vec![],
+            max_fields: usize::MAX,
+            num_cols: 0,
+            partial: vec![],
+        }
+    }
+
+    /// Use the first line as column headers. (default: false)
+    pub fn header_line(mut self, header_line: bool) -> Self {
+        self.header_line = header_line;
+        self
+    }
+
+    /// Set column headers. Lines with more fields get columns without headers.
+    pub fn headers(mut self, headers: &[&str]) -> Self {
+        self.headers = headers.iter().map(|h| h.to_string()).collect();
+        self
+    }
+
+    /// Split each line into at most this many fields. The last field gets the rest of the
+    /// line. (ex: for `ps` output, where commands contain spaces)
+    pub fn max_fields(mut self, max_fields: usize) -> Self {
+        self.max_fields = max_fields.max(1);
+        self
+    }
+
+    /// Configure the underlying [`Stream`]. (ex: to set a title, or a max_width)
+    pub fn configure<F>(mut self, configure: F) -> Self
+    where F: FnOnce(Stream<Fields, Out>) -> Stream<Fields, Out>
+    {
+        self.stream = configure(self.stream);
+        self
+    }
+
+    /// Run `command`, displaying its output as a table as it arrives. Returns when the command
+    /// exits, with a summary of the table and the command's exit status.
+    ///
+    /// The command's stdout is captured. Its stdin and stderr are inherited.
+    /// If no lines arrive for a while, any buffered rows are displayed, as with
+    /// [`Stream::receive`].
+    ///
+    /// ```no_run
+    /// # use std::{io, process::Command};
+    /// # use tablestream::*;
+    /// let table = LineStream::new(io::stdout(), Split::Whitespace).header_line(true).max_fields(11);
+    /// let (summary, status) = table.run(Command::new("ps").arg("aux"))?;
+    /// # Ok::<(), io::Error>(())
+    /// ```
+    pub fn run(mut self, command: &mut Command) -> io::Result<(Summary, ExitStatus)> {
+        let mut child = command.stdout(Stdio::piped()).spawn()?;
+        let stdout = child.stdout.take().ok_or_else(|| io::Error::other("no stdout to capture"))?;
+
+        // Read on another thread, so that we can display buffered rows while waiting for more:
+        let (sender, receiver) = mpsc::channel();
+        let reader = thread::spawn(move || -> io::Result<()> {
+            let mut stdout = BufReader::new(stdout);
+            loop {
+                let mut line = vec![];
+                if stdout.read_until(b'\n', &mut line)? == 0 { return Ok(()); }
+                if sender.send(line).is_err() { return Ok(()); }
+            }
+        });
+
+        let displayed = self.receive_lines(&receiver);
+        // If we can't display output, stop the command rather than leave it running:
+        if displayed.is_err() {
+            let _ = child.kill();
+        }
+        drop(receiver);
+        let status = child.wait();
+        let read = reader.join().map_err(|_| io::Error::other("reader thread panicked"))?;
+
+        displayed?;
+        read?;
+        Ok((self.finish()?, status?))
+    }
+
+    /// Display lines from `receiver` until the reader is done.
+    fn receive_lines(&mut self, receiver: &mpsc::Receiver<Vec<u8>>) -> io::Result<()> {
+        loop {
+            match receiver.recv_timeout(IDLE_FLUSH) {
+                Ok(line) => self.write_all(&line)?,
+                Err(RecvTimeoutError::Timeout) => self.flush()?,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+    }
+
+    /// Display any remaining text, and finish the table. See: [`Stream::finish`].
+    pub fn finish(mut self) -> io::Result<Summary> {
+        if !self.partial.is_empty() {
+            let line = std::mem::take(&mut self.partial);
+            self.line(&line)?;
+        }
+        self.stream.finish()
+    }
+
+    /// Add a row (or headers) from one line of text, without its line ending.
+    fn line(&mut self, line: &[u8]) -> io::Result<()> {
+        let line = String::from_utf8_lossy(line);
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        let fields = self.split.split(line, self.max_fields);
+
+        if self.header_line {
+            self.header_line = false;
+            self.headers = fields;
+            return self.add_columns(self.headers.len());
+        }
+
+        self.add_columns(fields.len())?;
+        self.stream.row(fields)
+    }
+
+    /// Make sure that we have at least `num_cols` columns.
+    fn add_columns(&mut self, num_cols: usize) -> io::Result<()> {
+        while self.num_cols < num_cols.max(self.headers.len()) {
+            let index = self.num_cols;
+            let mut col = Column::new(move |f, fields: &Fields| {
+                f.write_str(fields.get(index).map(String::as_str).unwrap_or(""))
+            });
+            if let Some(header) = self.headers.get(index) {
+                col = col.header(header);
+            }
+            self.stream.add_column(col)?;
+            self.num_cols += 1;
+        }
+        Ok(())
+    }
+}
+
+impl <Out: Write> Write for LineStream<Out> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while let Some(end) = rest.iter().position(|&b| b == b'\n') {
+            let partial_len = self.partial.len();
+            let mut line = std::mem::take(&mut self.partial);
+            line.extend_from_slice(&rest[..end]);
+            if let Err(err) = self.line(&line) {
+                // This line wasn't consumed, so keep its start for the caller to retry:
+                line.truncate(partial_len);
+                self.partial = line;
+                let consumed = buf.len() - rest.len();
+                return match consumed {
+                    0 => Err(err),
+                    _ => Ok(consumed),
+                };
+            }
+            rest = &rest[end + 1..];
+        }
+        self.partial.extend_from_slice(rest);
+        Ok(buf.len())
+    }
+
+    /// Display any buffered rows. See: [`Stream::flush`].
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
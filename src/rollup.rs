@@ -0,0 +1,92 @@
+//! Periodic aggregate rows for time-series data. See: [`Stream::rollup`](crate::Stream::rollup).
+
+use std::{
+    fmt::Write as FmtWrite,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::RowValue;
+
+/// When a row happened.
+pub(crate) type RowTime<T> = dyn Fn(&T) -> SystemTime;
+
+/// Counts (and sums) rows in the current interval.
+pub(crate) struct Rollup<T> {
+    interval: Duration,
+    time: Box<RowTime<T>>,
+    sums: Vec<(String, Box<RowValue<T>>)>,
+
+    interval_index: Option<u128>, // of the current interval, since the epoch.
+    count: usize,
+    totals: Vec<f64>,
+}
+
+impl <T> Rollup<T> {
+    pub(crate) fn new(interval: Duration, time: Box<RowTime<T>>) -> Self {
+        Self {
+            interval: interval.max(Duration::from_millis(1)),
+            time,
+            sums: vec![],
+            interval_index: None,
+            count: 0,
+            totals: vec![],
+        }
+    }
+
+    pub(crate) fn add_sum(&mut self, label: &str, value: Box<RowValue<T>>) {
+        self.sums.push((label.to_string(), value));
+        self.totals.push(0.0);
+    }
+
+    /// Count `row`. If it starts a new interval, returns the summary of the previous one.
+    ///
+    /// Rows whose time is before the current interval (ex: slightly out of order) are
+    /// counted in the current interval.
+    pub(crate) fn add(&mut self, row: &T) -> Option<String> {
+        let index = self.index_of((self.time)(row));
+        let finished = match self.interval_index {
+            Some(current) if index > current => self.take(),
+            Some(_) => None,
+            None => {
+                self.interval_index = Some(index);
+                None
+            },
+        };
+        if finished.is_some() {
+            self.interval_index = Some(index);
+        }
+
+        self.count += 1;
+        for ((_, value), total) in self.sums.iter().zip(self.totals.iter_mut()) {
+            *total += value(row);
+        }
+        finished
+    }
+
+    /// The summary of the current interval, if it has any rows. Resets the counts.
+    pub(crate) fn take(&mut self) -> Option<String> {
+        if self.count == 0 { return None; }
+        let start = self.interval_index? * self.interval.as_millis();
+        let end = start + self.interval.as_millis();
+
+        let plural = if self.count == 1 { "" } else { "s" };
+        let mut line = format!("{}–{}: {} row{}", time_of_day(start), time_of_day(end), self.count, plural);
+        for ((label, _), total) in self.sums.iter().zip(self.totals.iter_mut()) {
+            let _ = write!(line, ", {}: {}", label, total);
+            *total = 0.0;
+        }
+        self.count = 0;
+        Some(line)
+    }
+
+    fn index_of(&self, time: SystemTime) -> u128 {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        since_epoch.as_millis() / self.interval.as_millis()
+    }
+}
+
+/// Format milliseconds since the epoch as a UTC time of day. (ex: `13:05:00`)
+fn time_of_day(millis: u128) -> String {
+    let seconds = (millis / 1000) % (24 * 60 * 60);
+    format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
@@ -0,0 +1,150 @@
+//! Colors and text attributes for table output.
+
+use std::{
+    env,
+    io::{self, Write},
+};
+
+use crossterm::style::{Attribute, SetAttribute, SetBackgroundColor, SetForegroundColor};
+
+pub use crossterm::style::Color;
+
+/// Colors and attributes with which to display text in a terminal.
+///
+/// ```
+/// # use tablestream::{Color, Style};
+/// let warning = Style::yellow().bold();
+/// let stale = Style::default().dim();
+/// let selected = Style::default().fg(Color::Black).bg(Color::White);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    fg: Option<Color>,
+    bg: Option<Color>,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+}
+
+impl Style {
+    /// Red text.
+    pub fn red() -> Self { Self::default().fg(Color::Red) }
+
+    /// Green text.
+    pub fn green() -> Self { Self::default().fg(Color::Green) }
+
+    /// Yellow text.
+    pub fn yellow() -> Self { Self::default().fg(Color::Yellow) }
+
+    /// Blue text.
+    pub fn blue() -> Self { Self::default().fg(Color::Blue) }
+
+    /// Set the text (foreground) color.
+    pub fn fg(mut self, color: Color) -> Self {
+        self.fg = Some(color);
+        self
+    }
+
+    /// Set the background color.
+    pub fn bg(mut self, color: Color) -> Self {
+        self.bg = Some(color);
+        self
+    }
+
+    /// Bold (or bright) text.
+    pub fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    /// Dim (faint) text.
+    pub fn dim(mut self) -> Self {
+        self.dim = true;
+        self
+    }
+
+    /// Italic text.
+    pub fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+
+    /// Underlined text.
+    pub fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    /// This style, with anything it doesn't set taken from `base`.
+    pub(crate) fn or(self, base: Style) -> Style {
+        Style {
+            fg: self.fg.or(base.fg),
+            bg: self.bg.or(base.bg),
+            bold: self.bold || base.bold,
+            dim: self.dim || base.dim,
+            italic: self.italic || base.italic,
+            underline: self.underline || base.underline,
+        }
+    }
+
+    /// Is this the default style, which writes no escape codes?
+    pub(crate) fn is_plain(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Write the escape codes to start displaying text in this style.
+    pub(crate) fn write_start<W: Write + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        if let Some(color) = self.fg {
+            write!(out, "{}", SetForegroundColor(color))?;
+        }
+        if let Some(color) = self.bg {
+            write!(out, "{}", SetBackgroundColor(color))?;
+        }
+        let attributes = [
+            (self.bold, Attribute::Bold),
+            (self.dim, Attribute::Dim),
+            (self.italic, Attribute::Italic),
+            (self.underline, Attribute::Underlined),
+        ];
+        for (_, attribute) in attributes.iter().filter(|(set, _)| *set) {
+            write!(out, "{}", SetAttribute(*attribute))?;
+        }
+        Ok(())
+    }
+
+    /// Write the escape code to return to the terminal's default style.
+    pub(crate) fn write_end<W: Write + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        if self.is_plain() {
+            return Ok(());
+        }
+        write!(out, "{}", SetAttribute(Attribute::Reset))
+    }
+}
+
+/// When to display colors and other styles. See: [`Stream::color`](crate::Stream::color).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Only if output is a terminal, and the `NO_COLOR` environment variable isn't set.
+    /// (See: <https://no-color.org>, and [`Stream::stdout`](crate::Stream::stdout))
+    #[default]
+    Auto,
+    /// Always.
+    Always,
+    /// Never.
+    Never,
+}
+
+impl ColorChoice {
+    /// Should we display colors, given whether output is a `terminal`?
+    pub(crate) fn enabled(self, terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                !no_color && terminal
+            },
+        }
+    }
+}
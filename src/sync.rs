@@ -0,0 +1,194 @@
+//! Sharing a table between threads.
+
+use std::{
+    error::Error,
+    fmt,
+    io::{self, Write},
+    sync::mpsc,
+    thread,
+};
+
+use crate::{Stream, Summary};
+
+/// A handle that lets multiple threads submit rows to one table.
+///
+/// Since columns aren't required to be `Send`, the [`Stream`] is created and written
+/// on its own thread, which receives rows over a channel.
+///
+/// ```
+/// # use std::{io, thread};
+/// # use tablestream::*;
+/// struct Row { worker: usize, item: usize }
+///
+/// let table = SyncStream::new(|| Stream::new(io::stdout(), vec![
+///     col!(Row: .worker).header("Worker"),
+///     col!(Row: .item).header("Item"),
+/// ]));
+///
+/// thread::scope(|s| {
+///     for worker in 0..4 {
+///         let table = &table;
+///         s.spawn(move || {
+///             for item in 0..10 {
+///                 table.row(Row{ worker, item }).unwrap();
+///             }
+///         });
+///     }
+/// });
+///
+/// let summary = table.finish()?;
+/// assert_eq!(40, summary.rows);
+/// # Ok::<(), io::Error>(())
+/// ```
+pub struct SyncStream<T> {
+    sender: RowSender<T>,
+    writer: thread::JoinHandle<io::Result<Summary>>,
+}
+
+impl <T: Send + 'static> SyncStream<T> {
+    /// Start a writer thread for the Stream created by `make_stream`.
+    ///
+    /// Rows are queued without limit, so [`SyncStream::row`] never blocks.
+    pub fn new<F, Out>(make_stream: F) -> Self
+    where
+        F: FnOnce() -> Stream<T, Out> + Send + 'static,
+        Out: Write,
+    {
+        let (sender, receiver) = mpsc::channel();
+        Self::spawn(SenderKind::Unbounded(sender), receiver, make_stream)
+    }
+
+    /// Like [`SyncStream::new`], but queue at most `capacity` rows.
+    ///
+    /// When the queue is full, [`SyncStream::row`] blocks until the writer catches up,
+    /// and [`SyncStream::try_row`] returns [`TryRowError::Full`].
+    pub fn bounded<F, Out>(capacity: usize, make_stream: F) -> Self
+    where
+        F: FnOnce() -> Stream<T, Out> + Send + 'static,
+        Out: Write,
+    {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        Self::spawn(SenderKind::Bounded(sender), receiver, make_stream)
+    }
+
+    fn spawn<F, Out>(sender: SenderKind<T>, receiver: mpsc::Receiver<T>, make_stream: F) -> Self
+    where
+        F: FnOnce() -> Stream<T, Out> + Send + 'static,
+        Out: Write,
+    {
+        let writer = thread::spawn(move || {
+            let mut stream = make_stream();
+            stream.receive(&receiver)?;
+            stream.finish()
+        });
+
+        Self { sender: RowSender{ inner: sender }, writer }
+    }
+
+    /// Submit a row to the table.
+    ///
+    /// Returns an error if the writer thread has stopped. (ex: due to an I/O error,
+    /// which will be returned by [`SyncStream::finish`].)
+    pub fn row(&self, data: T) -> io::Result<()> {
+        self.sender.row(data)
+    }
+
+    /// Submit a row to the table, without blocking if the queue is full.
+    pub fn try_row(&self, data: T) -> Result<(), TryRowError<T>> {
+        self.sender.try_row(data)
+    }
+
+    /// Get a sender for submitting rows from threads which can't borrow this handle.
+    ///
+    /// Note: [`SyncStream::finish`] will wait until all senders have been dropped.
+    pub fn sender(&self) -> RowSender<T> {
+        self.sender.clone()
+    }
+
+    /// Wait for all rows to be written and finish the table.
+    pub fn finish(self) -> io::Result<Summary> {
+        drop(self.sender);
+        self.writer.join().map_err(|_| io::Error::other("table writer thread panicked"))?
+    }
+}
+
+/// Submits rows to a [`SyncStream`].
+pub struct RowSender<T> {
+    inner: SenderKind<T>,
+}
+
+enum SenderKind<T> {
+    Unbounded(mpsc::Sender<T>),
+    Bounded(mpsc::SyncSender<T>),
+}
+
+impl <T> RowSender<T> {
+    /// See: [`SyncStream::row`].
+    pub fn row(&self, data: T) -> io::Result<()> {
+        let result = match &self.inner {
+            SenderKind::Unbounded(sender) => sender.send(data),
+            SenderKind::Bounded(sender) => sender.send(data),
+        };
+        result.map_err(|_| {
+            io::Error::new(io::ErrorKind::BrokenPipe, "table writer thread has stopped")
+        })
+    }
+
+    /// See: [`SyncStream::try_row`].
+    pub fn try_row(&self, data: T) -> Result<(), TryRowError<T>> {
+        match &self.inner {
+            SenderKind::Unbounded(sender) => sender.send(data).map_err(|e| TryRowError::Stopped(e.0)),
+            SenderKind::Bounded(sender) => sender.try_send(data).map_err(|e| match e {
+                mpsc::TrySendError::Full(data) => TryRowError::Full(data),
+                mpsc::TrySendError::Disconnected(data) => TryRowError::Stopped(data),
+            }),
+        }
+    }
+}
+
+impl <T> Clone for RowSender<T> {
+    fn clone(&self) -> Self {
+        let inner = match &self.inner {
+            SenderKind::Unbounded(sender) => SenderKind::Unbounded(sender.clone()),
+            SenderKind::Bounded(sender) => SenderKind::Bounded(sender.clone()),
+        };
+        Self { inner }
+    }
+}
+
+/// A row that couldn't be submitted by [`SyncStream::try_row`]. The row is returned.
+pub enum TryRowError<T> {
+    /// The queue is full. You may retry later, or drop the row.
+    Full(T),
+    /// The writer thread has stopped.
+    Stopped(T),
+}
+
+impl <T> TryRowError<T> {
+    /// Get back the row that couldn't be submitted.
+    pub fn into_inner(self) -> T {
+        match self {
+            TryRowError::Full(data) | TryRowError::Stopped(data) => data,
+        }
+    }
+}
+
+impl <T> fmt::Debug for TryRowError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRowError::Full(_) => write!(f, "Full(..)"),
+            TryRowError::Stopped(_) => write!(f, "Stopped(..)"),
+        }
+    }
+}
+
+impl <T> fmt::Display for TryRowError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRowError::Full(_) => write!(f, "the table's row queue is full"),
+            TryRowError::Stopped(_) => write!(f, "table writer thread has stopped"),
+        }
+    }
+}
+
+impl <T> Error for TryRowError<T> {}
@@ -0,0 +1,82 @@
+//! Helpers for testing code that outputs tables.
+
+use std::fmt::Write;
+
+use crate::width::str_width;
+
+/// Assert that two tables are equal.
+///
+/// On failure, shows each line that differs with whitespace made visible, the display
+/// width of each line, and the column at which they first differ. This makes off-by-one
+/// padding errors much easier to find than the output of `assert_eq!`.
+///
+/// ```
+/// # use tablestream::assert_table_eq;
+/// let expected = "\
+/// ------
+/// | ok |
+/// ------
+/// ";
+/// assert_table_eq!(expected, "------\n| ok |\n------\n");
+/// ```
+#[macro_export]
+macro_rules! assert_table_eq {
+    ($expected:expr, $actual:expr $(,)?) => {
+        if let Some(diff) = $crate::testing::table_diff(&$expected, &$actual) {
+            panic!("tables are not equal:\n{}", diff);
+        }
+    };
+}
+
+/// Describe how two tables differ, or None if they're equal. See: [`assert_table_eq!`].
+pub fn table_diff(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+
+    let mut out = String::new();
+    let expected_lines: Vec<_> = expected.split('\n').collect();
+    let actual_lines: Vec<_> = actual.split('\n').collect();
+    let num_lines = expected_lines.len().max(actual_lines.len());
+
+    for i in 0..num_lines {
+        let expected = expected_lines.get(i).copied();
+        let actual = actual_lines.get(i).copied();
+        if expected == actual {
+            continue;
+        }
+
+        let _ = writeln!(out, "line {}:", i + 1);
+        let _ = writeln!(out, "  expected: {}", describe(expected));
+        let _ = writeln!(out, "  actual:   {}", describe(actual));
+        if let (Some(expected), Some(actual)) = (expected, actual) {
+            let column = first_difference(expected, actual);
+            // Line up with the start of the lines above: "  actual:   (width NN) `"
+            let prefix = format!("  actual:   (width {}) `", str_width(actual));
+            let _ = writeln!(out, "{:>1$} column {2}", "^", str_width(&prefix) + column + 1, column + 1);
+        }
+    }
+
+    let _ = writeln!(out, "(spaces are shown as '·')");
+    Some(out)
+}
+
+fn describe(line: Option<&str>) -> String {
+    match line {
+        None => "(missing)".to_string(),
+        Some(line) => format!("(width {}) `{}`", str_width(line), line.replace(' ', "·")),
+    }
+}
+
+/// The display column at which two lines first differ.
+fn first_difference(a: &str, b: &str) -> usize {
+    let mut column = 0;
+    let mut b_chars = b.chars();
+    for a_char in a.chars() {
+        if b_chars.next() != Some(a_char) {
+            break;
+        }
+        column += str_width(a_char.encode_utf8(&mut [0; 4]));
+    }
+    column
+}
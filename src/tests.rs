@@ -1,6 +1,6 @@
-use std::{io};
+use std::{cell::RefCell, io::{self, Write}, rc::Rc, time::Duration};
 
-use crate::{Column, Stream, col};
+use crate::{Alignment, Color, ColorChoice, Column, ColumnStats, Degrade, Direction, Format, Frame, HeaderCase, LineStream, Notification, Position, Profile, Split, Status, StatusFormat, Stream, Style, Theme, Truncate, assert_table_eq, col};
 
 
 struct Person {
@@ -35,6 +35,15 @@ fn sample_data() -> Vec<Person> {
     ]
 }
 
+/// A Write that we can still read after the Stream takes it.
+#[derive(Clone, Default)]
+struct Shared(Rc<RefCell<Vec<u8>>>);
+
+impl io::Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.borrow_mut().write(buf) }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
 fn cols_3() -> Vec<Column<Person>> {
     vec![
         col!(Person: .name).header("Name"),
@@ -72,7 +81,7 @@ fn basic() -> io::Result<()> {
 ---------------------------
 Name | Age | Favorite Color
 ---------------------------
-Cody | 41  | yellow        
+Cody |  41 | yellow        
 ---------------------------
 ".trim_start();
 
@@ -123,7 +132,7 @@ fn basic_border() -> io::Result<()> {
 -------------------------------
 | Name | Age | Favorite Color |
 -------------------------------
-| Cody | 41  | yellow         |
+| Cody |  41 | yellow         |
 -------------------------------
 ";
 
@@ -154,7 +163,7 @@ fn title() -> io::Result<()> {
 -------------------------------
 | Name | Age | Favorite Color |
 -------------------------------
-| Cody | 41  | yellow         |
+| Cody |  41 | yellow         |
 -------------------------------
 ";
 
@@ -183,8 +192,8 @@ fn basic_border_grow() -> io::Result<()> {
 --------------------------------------------------------------------------------
 |         Name         |         Age         |         Favorite Color          |
 --------------------------------------------------------------------------------
-| Cody                 | 41                  | yellow                          |
-| Bob                  | 99                  | beige                           |
+| Cody                 |                  41 | yellow                          |
+| Bob                  |                  99 | beige                           |
 --------------------------------------------------------------------------------
 ";
 
@@ -243,8 +252,8 @@ fn longer_text_border() -> io::Result<()> {
 --------------------------------------------------------------------------------
 | Name | Age | Favorite Color |                      Text                      |
 --------------------------------------------------------------------------------
-| Cody | 41  | yellow         | Here's a long string of text. It's probably go |
-| Bob  | 99  | beige          | lorum ipsum dolor sit amet. Or something to th |
+| Cody |  41 | yellow         | Here's a long string of text. It's probably go |
+| Bob  |  99 | beige          | lorum ipsum dolor sit amet. Or something to th |
 --------------------------------------------------------------------------------
 ";
 
@@ -272,8 +281,8 @@ fn longer_text() -> io::Result<()> {
 --------------------------------------------------------------------------------
 Name | Age | Favorite Color |                        Text                       
 --------------------------------------------------------------------------------
-Cody | 41  | yellow         | Here's a long string of text. It's probably going 
-Bob  | 99  | beige          | lorum ipsum dolor sit amet. Or something to that e
+Cody |  41 | yellow         | Here's a long string of text. It's probably going 
+Bob  |  99 | beige          | lorum ipsum dolor sit amet. Or something to that e
 --------------------------------------------------------------------------------
 ";
 
@@ -318,10 +327,10 @@ fn unicode_text() -> io::Result<()> {
 --------------------------------------------------------------------------------
 |  Name  | Age | Favorite Color |                     Text                     |
 --------------------------------------------------------------------------------
-| Cody   | 41  | yellow         | Here's a long string of text. It's probably  |
-| Bob    | 99  | beige          | lorum ipsum dolor sit amet. Or something to  |
-| Heiðar | 37  | rauður         | Okej, þú ert hérna núna. :)                  |
-| 陽葵   | 37  | 青             | ここにいくつかの日本語のテキストがあります。 |
+| Cody   |  41 | yellow         | Here's a long string of text. It's probably  |
+| Bob    |  99 | beige          | lorum ipsum dolor sit amet. Or something to  |
+| Heiðar |  37 | rauður         | Okej, þú ert hérna núna. :)                  |
+| 陽葵   |  37 | 青             | ここにいくつかの日本語のテキストがあります。 |
 --------------------------------------------------------------------------------
 ";
 
@@ -331,6 +340,32 @@ fn unicode_text() -> io::Result<()> {
     Ok(())
 }
 
+#[test]
+fn unicode_headers() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("名前"),
+        col!(Person: .favorite_color).header("Couleur préférée"),
+    ]).borders(true);
+
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+---------------------------
+| 名前 | Couleur préférée |
+---------------------------
+| Cody | yellow           |
+| Bob  | beige            |
+---------------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
 #[test]
 fn min_width() -> io::Result<()> {
     let mut out = Vec::new();
@@ -353,8 +388,8 @@ fn min_width() -> io::Result<()> {
 --------------------------------------------------
 Name | Age | F |           Text            | Short
 --------------------------------------------------
-Cody | 41  | y | Here's a long string of t | Here'
-Bob  | 99  | b | lorum ipsum dolor sit ame | lorum
+Cody |  41 | y | Here's a long string of t | Here'
+Bob  |  99 | b | lorum ipsum dolor sit ame | lorum
 --------------------------------------------------
 ";
 
@@ -363,4 +398,3408 @@ Bob  | 99  | b | lorum ipsum dolor sit ame | lorum
     assert_eq!(expected, out);
 
     Ok(())
-}
\ No newline at end of file
+}
+#[test]
+fn max_height() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).borders(true).max_width(80).max_height(1);
+
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.row(sample_data().remove(0))?;
+
+    s.footer("3 people")?;
+
+    let expected = "\
+-------------------------------
+| Name | Age | Favorite Color |
+-------------------------------
+| Cody |  41 | yellow         |
+| … (2 more)                  |
+-------------------------------
+|          3 people           |
+-------------------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn fit_screen_height() {
+    let mut out = Vec::new();
+    let s = Stream::new(&mut out, cols_3()).fit_screen();
+    // 24 lines - 8 lines of decorations (rules, headers, "more" row, footer, prompt):
+    assert_eq!(16, s.rows_for_height(24));
+    assert_eq!(14, s.title("People").rows_for_height(24));
+}
+
+#[test]
+fn memoize() -> io::Result<()> {
+    use std::{cell::Cell, rc::Rc};
+
+    let calls = Rc::new(Cell::new(0));
+    let counter = calls.clone();
+    let color_col = Column::new(move |f, p: &Person| {
+        counter.set(counter.get() + 1);
+        write!(f, "{}", p.favorite_color)
+    }).header("Favorite Color").memoize(|p: &Person| p.favorite_color.clone());
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![col!(Person: .name).header("Name"), color_col]);
+    for person in sample_data().into_iter().chain(sample_data()) {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+---------------------
+Name | Favorite Color
+---------------------
+Cody | yellow        
+Bob  | beige         
+Cody | yellow        
+Bob  | beige         
+---------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+    // Only once per distinct color:
+    assert_eq!(2, calls.get());
+
+    Ok(())
+}
+
+#[test]
+fn ascii_width_fast_path() {
+    use unicode_truncate::UnicodeTruncateStr;
+    use unicode_width::UnicodeWidthStr;
+    use crate::width::{str_width, truncate};
+
+    for value in ["", "hello", "Hello, world! ~{}", "tab\there", "new\nline"] {
+        assert_eq!(value.width(), str_width(value), "{:?}", value);
+        for width in 0..20 {
+            let (fits, fits_width) = truncate(value, width);
+            assert_eq!(str_width(fits), fits_width, "{:?}", value);
+            assert!(fits_width <= width, "{:?}", value);
+            if !value.contains(char::is_control) {
+                assert_eq!(value.unicode_truncate(width), (fits, fits_width), "{:?}", value);
+            }
+        }
+    }
+
+    assert_eq!(4, str_width("陽葵"));
+    assert_eq!(("陽", 2), truncate("陽葵", 3));
+}
+
+#[test]
+fn raw_line() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).borders(true).max_width(80);
+
+    let mut people = sample_data().into_iter();
+    s.row(people.next().unwrap())?;
+    s.raw_line("~~ Old people: ~~~~~~~~~~~~~~~~~~~~~~~")?;
+    s.row(people.next().unwrap())?;
+    s.finish()?;
+
+    let expected = "\
+-------------------------------
+| Name | Age | Favorite Color |
+-------------------------------
+| Cody |  41 | yellow         |
+| ~~ Old people: ~~~~~~~~~~~~ |
+| Bob  |  99 | beige          |
+-------------------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn summary_row() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).borders(true).max_width(80);
+
+    s.summary_row(Person {
+        name: "Total".to_string(),
+        age: 140,
+        favorite_color: "".to_string(),
+        text: "".to_string(),
+    });
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+--------------------------------
+| Name  | Age | Favorite Color |
+--------------------------------
+| Cody  |  41 | yellow         |
+| Bob   |  99 | beige          |
+--------------------------------
+| Total | 140 |                |
+--------------------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn plan_and_layout() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).borders(true).max_width(80);
+
+    let mut layout = s.plan(&sample_data())?;
+    assert_eq!(vec![4, 3, 14], layout.widths);
+
+    // Nothing is written while planning, so we can still adjust the layout:
+    layout.widths[0] = 6;
+    let mut s = s.layout(layout);
+    for person in sample_data().into_iter().take(1) {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+---------------------------------
+|  Name  | Age | Favorite Color |
+---------------------------------
+| Cody   | 41  | yellow         |
+---------------------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn layout_hidden_column() -> io::Result<()> {
+    // Plan with every column, then hide one when displaying:
+    let layout = Stream::new(Vec::new(), cols_3()).borders(true).max_width(80).plan(&sample_data())?;
+    let mut cols = cols_3();
+    cols[1] = col!(Person: .age).header("Age").hidden();
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols).borders(true).max_width(80).layout(layout);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+-------------------------
+| Name | Favorite Color |
+-------------------------
+| Cody | yellow         |
+| Bob  | beige          |
+-------------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn elide_prefix() -> io::Result<()> {
+    struct File { path: &'static str }
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(File: .path).header("Path").elide_prefix(),
+    ]).max_width(80);
+
+    s.row(File{ path: "/home/user/project/src/lib.rs" })?;
+    s.row(File{ path: "/home/user/project/src/tests.rs" })?;
+    s.row(File{ path: "/home/user/project/Cargo.toml" })?;
+    s.finish()?;
+
+    let expected = "\
+------------
+    Path    
+------------
+src/lib.rs  
+src/tests.rs
+Cargo.toml  
+------------
+Path: values start with \"/home/user/project/\"
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn footnotes() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_4()).max_width(50).footnotes(true);
+
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+--------------------------------------------------
+Name | Age | Favorite Color |         Text        
+--------------------------------------------------
+Cody |  41 | yellow         | Here's a long str[1]
+Bob  |  99 | beige          | lorum ipsum dolor[2]
+--------------------------------------------------
+[1] Here's a long string of text. It's probably going to be too long to fit on-screen without wrapping. It should probably get truncated.
+[2] lorum ipsum dolor sit amet. Or something to that effect. I don't speak Latin so it's hard to remember that text off the top of my head.
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn untruncated_width() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_4()).borders(true).max_width(80);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    let summary = s.finish()?;
+
+    assert_eq!(2, summary.rows);
+    let longest_text = sample_data().iter().map(|p| p.text.len()).max().unwrap();
+    // "| Name | Age | Favorite Color | " + the longest text + " |":
+    assert_eq!(32 + longest_text + 2, summary.untruncated_width);
+
+    // Rows hidden by max_height are measured too:
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_4()).borders(true).max_width(80).max_height(1);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    let summary = s.finish()?;
+    assert_eq!(32 + longest_text + 2, summary.untruncated_width);
+
+    Ok(())
+}
+
+#[test]
+fn templates() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_4()).max_width(50).title("{rows} People");
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.footer("{rows} rows, {truncated} truncated")?;
+
+    let expected = "\
+--------------------------------------------------
+                     2 People                     
+--------------------------------------------------
+Name | Age | Favorite Color |         Text        
+--------------------------------------------------
+Cody |  41 | yellow         | Here's a long string
+Bob  |  99 | beige          | lorum ipsum dolor si
+--------------------------------------------------
+               2 rows, 2 truncated                
+--------------------------------------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn elapsed() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3());
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    let summary = s.footer("took {elapsed}")?;
+
+    assert!(summary.elapsed >= std::time::Duration::from_millis(10));
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("took "), "{}", out);
+    assert!(!out.contains("{elapsed}"), "{}", out);
+
+    Ok(())
+}
+
+#[test]
+fn hidden_column() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut cols = cols_4();
+    cols[3] = col!(Person: .text).header("Text").hidden();
+    let mut s = Stream::new(&mut out, cols).borders(true).max_width(80);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+-------------------------------
+| Name | Age | Favorite Color |
+-------------------------------
+| Cody |  41 | yellow         |
+| Bob  |  99 | beige          |
+-------------------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn add_column() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).borders(true).max_width(80).title("People");
+    let layout = s.plan(&sample_data())?;
+    let mut s = s.layout(layout);
+
+    let mut people = sample_data().into_iter();
+    s.row(people.next().unwrap())?;
+    s.add_column(col!(Person: .text).header("Text").min_width(10))?;
+    s.row(people.next().unwrap())?;
+    s.finish()?;
+
+    let expected = "\
+-------------------------------
+|           People            |
+-------------------------------
+| Name | Age | Favorite Color |
+-------------------------------
+| Cody | 41  | yellow         |
+--------------------------------------------
+|                  People                  |
+--------------------------------------------
+| Name | Age | Favorite Color |    Text    |
+--------------------------------------------
+| Bob  | 99  | beige          | lorum ipsu |
+--------------------------------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn right_to_left() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut cols = cols_3();
+    cols[1] = col!(Person: .age).header("Age").left();
+    let mut s = Stream::new(&mut out, cols).borders(true).max_width(80).direction(Direction::Rtl);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+-------------------------------
+| Favorite Color | Age | Name |
+-------------------------------
+|         yellow | 41  | Cody |
+|          beige | 99  |  Bob |
+-------------------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn title_wrap() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3())
+        .borders(true)
+        .max_width(20)
+        .title("Some people and their favorite colors")
+        .title_wrap(true);
+
+    for person in sample_data().into_iter().take(1) {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+-------------------------------
+|    Some people and their    |
+|       favorite colors       |
+-------------------------------
+| Name | Age | Favorite Color |
+-------------------------------
+| Cody |  41 | yellow         |
+-------------------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn wrap_text() {
+    use crate::width::wrap;
+
+    assert_eq!(vec!["Some people", "and their", "favorite", "colors"], wrap("Some people and their favorite colors", 11));
+    assert_eq!(vec!["abcd", "efgh", "ij"], wrap("abcdefghij", 4));
+    assert_eq!(vec!["陽", "葵"], wrap("陽葵", 1));
+    assert_eq!(vec![""], wrap("   ", 5));
+}
+
+#[test]
+fn custom_divider() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_4()).max_width(50).divider(" │ ");
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+--------------------------------------------------
+Name │ Age │ Favorite Color │         Text        
+--------------------------------------------------
+Cody │  41 │ yellow         │ Here's a long string
+Bob  │  99 │ beige          │ lorum ipsum dolor si
+--------------------------------------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn print_schema() -> io::Result<()> {
+    let mut out = Vec::new();
+    Stream::new(&mut out, cols_4()).borders(true).title("People").print_schema()?;
+
+    let expected = "\
+--------------------------------------
+|               People               |
+--------------------------------------
+| Name | Age | Favorite Color | Text |
+--------------------------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn suppress_rows() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).borders(true).suppress_rows(true);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.footer("{rows} people")?;
+
+    let expected = "\
+-------------------------------
+| Name | Age | Favorite Color |
+-------------------------------
+|          2 people           |
+-------------------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn collapse_duplicates() -> io::Result<()> {
+    struct Log { level: &'static str, message: &'static str }
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Log: .level).header("Level"),
+        col!(Log: .message).header("Message"),
+    ]).max_width(80).collapse_duplicates(|l: &Log| (l.level, l.message));
+
+    s.row(Log{ level: "INFO", message: "Starting" })?;
+    for _ in 0..12 {
+        s.row(Log{ level: "WARN", message: "Retrying" })?;
+    }
+    s.row(Log{ level: "INFO", message: "Done" })?;
+    s.row(Log{ level: "INFO", message: "Done" })?;
+    s.finish()?;
+
+    let expected = "\
+----------------------
+    | Level | Message 
+----------------------
+    | INFO  | Starting
+×12 | WARN  | Retrying
+ ×2 | INFO  | Done    
+----------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn validate() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut cols = cols_3();
+    cols[1] = col!(Person: .age).header("Age").validate(|p: &Person| {
+        if p.age < 90 { Ok(()) } else { Err(format!("{} is too old", p.age)) }
+    });
+    let mut s = Stream::new(&mut out, cols).borders(true);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    let summary = s.finish()?;
+
+    let expected = "\
+-------------------------------
+| Name | Age | Favorite Color |
+-------------------------------
+| Cody |  41 | yellow         |
+| Bob  | !99 | beige          |
+-------------------------------
+! Row 2, Age: 99 is too old
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+    assert_eq!(1, summary.validation_errors.len());
+    assert_eq!(2, summary.validation_errors[0].row);
+
+    Ok(())
+}
+
+#[test]
+fn validate_hidden_rows() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("Name"),
+        col!(Person: .favorite_color).header("Color").validate(|p: &Person| {
+            Err(format!("{} is not a primary color", p.favorite_color))
+        }),
+    ]).borders(true).max_height(1);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    let summary = s.finish()?;
+
+    // Markers don't truncate values, and hidden rows are still validated:
+    let expected = "\
+------------------
+| Name |  Color  |
+------------------
+| Cody | !yellow |
+| … (1 more)     |
+------------------
+! Row 1, Color: yellow is not a primary color
+! Row 2, Color: beige is not a primary color
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+    assert_eq!(2, summary.validation_errors.len());
+    assert_eq!(2, summary.validation_errors[1].row);
+
+    Ok(())
+}
+
+#[test]
+fn receive() -> io::Result<()> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let producer = std::thread::spawn(move || {
+        for person in sample_data() {
+            sender.send(person).unwrap();
+        }
+    });
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3());
+    s.receive(&receiver)?;
+    s.finish()?;
+    producer.join().unwrap();
+
+    let expected = "\
+---------------------------
+Name | Age | Favorite Color
+---------------------------
+Cody |  41 | yellow        
+Bob  |  99 | beige         
+---------------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn sync_stream() -> io::Result<()> {
+    use std::sync::{Arc, Mutex};
+    use crate::SyncStream;
+
+    /// Lets us see the output after the writer thread is done with it.
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+    impl io::Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.lock().unwrap().write(buf) }
+        fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    }
+
+    let out = SharedBuf::default();
+    let writer_out = out.clone();
+    let table = SyncStream::new(move || Stream::new(writer_out, vec![
+        col!(Person: .name).header("Name"),
+    ]));
+
+    std::thread::scope(|s| {
+        for person in sample_data() {
+            let table = &table;
+            s.spawn(move || table.row(person).unwrap());
+        }
+    });
+    let summary = table.finish()?;
+    assert_eq!(2, summary.rows);
+
+    let out = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+    assert!(out.contains("Cody"), "{}", out);
+    assert!(out.contains("Bob"), "{}", out);
+
+    Ok(())
+}
+
+#[test]
+fn sync_stream_bounded() -> io::Result<()> {
+    use std::sync::{Arc, Barrier};
+    use crate::{SyncStream, TryRowError};
+
+    // Keep the writer from starting until we've filled the queue:
+    let barrier = Arc::new(Barrier::new(2));
+    let writer_barrier = barrier.clone();
+    let table = SyncStream::bounded(1, move || {
+        writer_barrier.wait();
+        Stream::new(io::sink(), cols_3())
+    });
+
+    let mut people = sample_data().into_iter();
+    table.try_row(people.next().unwrap()).unwrap();
+    let full = table.try_row(people.next().unwrap());
+    assert!(matches!(full, Err(TryRowError::Full(_))));
+
+    barrier.wait();
+    table.row(full.unwrap_err().into_inner())?;
+    let summary = table.finish()?;
+    assert_eq!(2, summary.rows);
+
+    Ok(())
+}
+
+#[test]
+fn max_rows_per_second() -> io::Result<()> {
+    // Output that isn't a terminal isn't paced:
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).borders(true).max_rows_per_second(1);
+    let start = std::time::Instant::now();
+    for person in sample_data().into_iter().chain(sample_data()) {
+        s.row(person)?;
+    }
+    let summary = s.finish()?;
+    assert_eq!(4, summary.rows);
+    assert!(start.elapsed() < std::time::Duration::from_secs(1));
+
+    let expected = "\
+-------------------------------
+| Name | Age | Favorite Color |
+-------------------------------
+| Cody |  41 | yellow         |
+| Bob  |  99 | beige          |
+| Cody |  41 | yellow         |
+| Bob  |  99 | beige          |
+-------------------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn quiet() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).quiet(true);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    let summary = s.finish()?;
+    assert_eq!(2, summary.rows);
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!("2 rows\n", out);
+
+    Ok(())
+}
+
+#[test]
+fn typed_column() -> io::Result<()> {
+    use crate::NumberFormat;
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("Name"),
+        Column::typed(|p: &Person| p.age as f32 / 10.0, NumberFormat::default().precision(2)).header("Decades"),
+        Column::typed(|p: &Person| p.age, |f: &mut std::fmt::Formatter, age: &u8| write!(f, "{} years", age)).header("Age"),
+    ]).borders(true);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+-----------------------------
+| Name | Decades |   Age    |
+-----------------------------
+| Cody |    4.10 | 41 years |
+| Bob  |    9.90 | 99 years |
+-----------------------------
+";
+
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn table_diff() {
+    use crate::testing::table_diff;
+
+    let expected = "\
+------
+| ok |
+------
+";
+    let actual = "\
+------
+| ok  |
+------
+";
+    assert_table_eq!(expected, expected);
+
+    let diff = table_diff(expected, actual).unwrap();
+    let expected_diff = "\
+line 2:
+  expected: (width 6) `|·ok·|`
+  actual:   (width 7) `|·ok··|`
+                            ^ column 6
+(spaces are shown as '·')
+";
+    assert_eq!(expected_diff, diff);
+}
+
+#[test]
+fn strict_layout() -> io::Result<()> {
+    // Exercise the layouts most likely to go wrong: truncation, wide chars, footers.
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_4())
+        .max_width(30)
+        .borders(true)
+        .title("{rows} People")
+        .strict_layout(true);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.row(Person { name: "陽葵".into(), age: 7, favorite_color: "水色".into(), text: "こんにちは".into() })?;
+    s.footer("{rows} rows")?;
+
+    let out = String::from_utf8(out).unwrap();
+    let width = crate::width::str_width(out.lines().next().unwrap());
+    for line in out.lines() {
+        assert_eq!(width, crate::width::str_width(line), "{:?}", line);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn no_configuration_panics() -> io::Result<()> {
+    for max_width in [0, 1, 5, 12, 30, 80] {
+        for options in 0..64u32 {
+            let flag = |bit: u32| options & (1 << bit) != 0;
+
+            let mut cols = cols_4();
+            if flag(0) { cols[3] = col!(Person: .text).header("Text").min_width(20); }
+            if flag(1) { cols[1] = col!(Person: .age).header("Age").hidden(); }
+
+            let mut out = Vec::new();
+            let mut s = Stream::new(&mut out, cols)
+                .max_width(max_width)
+                .borders(flag(2))
+                .padding(flag(3))
+                .footnotes(flag(4))
+                .direction(if flag(5) { Direction::Rtl } else { Direction::Ltr })
+                .title("A title that is quite a bit longer than some tables")
+                .title_wrap(max_width % 2 == 0)
+                .grow(max_width > 12)
+                .strict_layout(true);
+            for person in sample_data() {
+                s.row(person)?;
+            }
+            s.footer("{rows} rows, {truncated} truncated")?;
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn control_characters() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3());
+    s.row(Person { name: "Line\nBreak".into(), age: 1, favorite_color: "tab\there".into(), text: "".into() })?;
+    s.finish()?;
+
+    let expected = "\
+---------------------------------
+   Name    | Age | Favorite Color
+---------------------------------
+Line␊Break |   1 | tab␉here      
+---------------------------------
+";
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn ansi_escapes() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("Name"),
+        col!(Person: .text).header("Status"),
+    ]).max_width(14);
+    s.row(Person { name: "\x1b[1mBob\x1b[0m".into(), age: 1, favorite_color: "".into(), text: "\x1b[31mFailed early\x1b[0m".into() })?;
+    s.row(Person { name: "Alice".into(), age: 2, favorite_color: "".into(), text: "\x1b[32mOK\x1b[0m".into() })?;
+    s.row(Person { name: "Eve".into(), age: 3, favorite_color: "".into(), text: "\x1b[2Jcleared".into() })?;
+    s.finish()?;
+
+    let expected = "\
+--------------
+Name  | Status
+--------------
+\x1b[1mBob\x1b[0m   | \x1b[31mFailed\x1b[0m
+Alice | \x1b[32mOK\x1b[0m    
+Eve   | ␛[2Jcl
+--------------
+";
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn auto_hidden() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("Name"),
+        col!(Person: .age).header("Age").hidden(),
+        col!(Person: .favorite_color).header("Color").min_width(6).degrade(&[Degrade::Hide]),
+    ]).max_width(8);
+
+    // Columns hidden to fit one layout aren't hidden from the next:
+    let layout = s.plan(&sample_data())?;
+    assert_eq!(0, layout.widths[2]);
+    let mut s = s.max_width(20);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+-------------
+Name | Color 
+-------------
+Cody | yellow
+Bob  | beige 
+-------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn degrade() -> io::Result<()> {
+    let render = |max_width| -> io::Result<String> {
+        let mut out = Vec::new();
+        let mut s = Stream::new(&mut out, vec![
+            col!(Person: .name).header("Name"),
+            col!(Person: .text).header("Text").min_width(6)
+                .degrade(&[Degrade::Wrap, Degrade::Truncate, Degrade::Hide]),
+        ]).max_width(max_width);
+        for person in sample_data() {
+            s.row(person)?;
+        }
+        s.finish()?;
+        Ok(String::from_utf8(out).unwrap())
+    };
+
+    // Wide enough to wrap without breaking words:
+    let expected = "\
+----------------------------------------
+Name |               Text               
+----------------------------------------
+Cody | Here's a long string of text.    
+     | It's probably going to be too    
+     | long to fit on-screen without    
+     | wrapping. It should probably get 
+     | truncated.                       
+Bob  | lorum ipsum dolor sit amet. Or   
+     | something to that effect. I don't
+     | speak Latin so it's hard to      
+     | remember that text off the top of
+     | my head.                         
+----------------------------------------
+";
+    assert_eq!(expected, render(40)?);
+
+    // Too narrow to wrap words, so truncate:
+    let expected = "\
+--------------
+Name |  Text  
+--------------
+Cody | Here's 
+Bob  | lorum i
+--------------
+";
+    assert_eq!(expected, render(14)?);
+
+    // Too narrow for min_width, so hide:
+    let expected = "\
+----
+Name
+----
+Cody
+Bob 
+----
+";
+    assert_eq!(expected, render(8)?);
+
+    Ok(())
+}
+
+#[test]
+fn wrap() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_4()).max_width(50).wrap(true).borders(true);
+    let cody = sample_data().remove(0);
+    s.row(cody)?;
+    s.finish()?;
+
+    let expected = "\
+--------------------------------------------------
+| Name | Age | Favorite Color |       Text       |
+--------------------------------------------------
+| Cody |  41 | yellow         | Here's a long    |
+|      |     |                | string of text.  |
+|      |     |                | It's probably    |
+|      |     |                | going to be too  |
+|      |     |                | long to fit      |
+|      |     |                | on-screen        |
+|      |     |                | without          |
+|      |     |                | wrapping. It     |
+|      |     |                | should probably  |
+|      |     |                | get truncated.   |
+--------------------------------------------------
+";
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn record_view() -> io::Result<()> {
+    let view = Shared::default();
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_4()).max_width(50).record_view(view.clone());
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+Name,Age,Favorite Color,Text
+Cody,41,yellow,Here's a long string
+Bob,99,beige,lorum ipsum dolor si
+";
+    let view = String::from_utf8(view.0.borrow().clone()).unwrap();
+    assert_eq!(expected, view);
+
+    // Reopened tables don't repeat the header record:
+    let view = Shared::default();
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).record_view(view.clone());
+    let mut people = sample_data().into_iter();
+    s.row(people.next().unwrap())?;
+    s.flush()?;
+    s.add_column(col!(Person: .name).header("Again"))?;
+    s.row(people.next().unwrap())?;
+    s.finish()?;
+
+    let expected = "\
+Name,Age,Favorite Color
+Cody,41,yellow
+Bob,99,beige,Bob
+";
+    let view = String::from_utf8(view.0.borrow().clone()).unwrap();
+    assert_eq!(expected, view);
+
+    Ok(())
+}
+
+#[test]
+fn csv_quoting() -> io::Result<()> {
+    let mut out = Vec::new();
+    crate::csv::write_record(&mut out, ["plain", "a, b", "say \"hi\"", "two\nlines", ""])?;
+    assert_eq!("plain,\"a, b\",\"say \"\"hi\"\"\",\"two\nlines\",\n", String::from_utf8(out).unwrap());
+    Ok(())
+}
+
+#[test]
+fn pin() -> io::Result<()> {
+    let person = |name: &str, age| Person { name: name.into(), age, favorite_color: "".into(), text: "".into() };
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).grow(false);
+    s.pin(person("Total", 140), Position::Bottom)?;
+    s.pin(person("Oldest", 99), Position::Top)?;
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.flush()?;
+    assert!(s.pin(person("Youngest", 41), Position::Top).is_err());
+    s.finish()?;
+
+    let expected = "\
+-----------------------------
+ Name  | Age | Favorite Color
+-----------------------------
+Oldest |  99 |               
+-----------------------------
+Cody   |  41 | yellow        
+Bob    |  99 | beige         
+-----------------------------
+Total  | 140 |               
+-----------------------------
+";
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(expected, out);
+
+    Ok(())
+}
+
+#[test]
+fn only_for() -> io::Result<()> {
+    let columns = || vec![
+        col!(Person: .name).header("Name"),
+        Column::new(|f, p: &Person| write!(f, "{} years", p.age)).header("Age").only_for(Format::Table),
+        col!(Person: .age).header("Age").only_for(Format::Csv),
+        col!(Person: .favorite_color).header("Favorite Color").hidden(),
+    ];
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, columns());
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+---------------
+Name |   Age   
+---------------
+Cody | 41 years
+Bob  | 99 years
+---------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, columns()).format(Format::Csv);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+Name,Age,Favorite Color
+Cody,41,yellow
+Bob,99,beige
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    // Tables in other formats show their own columns:
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("Name"),
+        Column::new(|f, p: &Person| write!(f, "{} years", p.age)).header("Age").only_for(Format::Table),
+        col!(Person: .age).header("Age").only_for(Format::Rst),
+    ]).format(Format::Rst);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
++------+-----+
+| Name | Age |
++======+=====+
+| Cody |  41 |
++------+-----+
+| Bob  |  99 |
++------+-----+
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn theme() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3())
+        .borders(true)
+        .theme(Theme::unicode().header_rule('═'))
+        .title("People");
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.footer("2 people")?;
+
+    let expected = "\
+┌─────────────────────────────┐
+│           People            │
+├──────┬─────┬────────────────┤
+│ Name │ Age │ Favorite Color │
+├══════┼═════┼════════════════┤
+│ Cody │  41 │ yellow         │
+│ Bob  │  99 │ beige          │
+├──────┴─────┴────────────────┤
+│          2 people           │
+└─────────────────────────────┘
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn junctions() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3())
+        .theme(Theme::default().junctions(['+', '+', '+', '+', '+']));
+    s.pin(sample_data().remove(1), Position::Top)?;
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+-----+-----+---------------
+Name | Age | Favorite Color
+-----+-----+---------------
+Bob  |  99 | beige         
+-----+-----+---------------
+Cody |  41 | yellow        
+Bob  |  99 | beige         
+-----+-----+---------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn digits() -> io::Result<()> {
+    use crate::{Digits, NumberFormat};
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("Name"),
+        Column::typed(|p: &Person| p.age * 2, NumberFormat::default().digits(Digits::ArabicIndic)).header("Age*2"),
+        Column::typed(|p: &Person| p.age as f32 / 10.0, NumberFormat::default().precision(1).digits(Digits::Persian)).header("Decades"),
+    ]).borders(true);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+--------------------------
+| Name | Age*2 | Decades |
+--------------------------
+| Cody |    ٨٢ |     ۴٫۱ |
+| Bob  |   ١٩٨ |     ۹٫۹ |
+--------------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn fill() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).fill('.').min_width(10),
+        col!(Person: .age).fill('.').right().min_width(5),
+        col!(Person: .favorite_color).fill('-').center().min_width(10),
+    ]).divider(" ");
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+---------------------------
+Cody ..... .. 41 - yellow -
+Bob ...... .. 99 - beige --
+---------------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn tsv() -> io::Result<()> {
+    assert_eq!(Ok(Format::Tsv), "TSV".parse());
+    assert!("xml".parse::<Format>().is_err());
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).format(Format::Tsv);
+    s.row(Person { name: "Tab\tby\\Tab".into(), age: 1, favorite_color: "two\nlines".into(), text: "".into() })?;
+    s.finish()?;
+
+    let expected = "\
+Name\tAge\tFavorite Color
+Tab\\tby\\\\Tab\t1\ttwo\\nlines
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn html() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("Name"),
+        col!(Person: .age).header("Age").right(),
+        col!(Person: .text).header("<Text>").hidden(),
+    ]).format(Format::Html).title("People");
+    for person in sample_data() {
+        s.row(Person { text: "Tom & Jerry".into(), ..person })?;
+    }
+    s.footer("{rows} people")?;
+
+    let expected = r#"<table>
+<caption>People</caption>
+<thead>
+<tr><th>Name</th><th style="text-align:right">Age</th><th>&lt;Text&gt;</th></tr>
+</thead>
+<tbody>
+<tr><td>Cody</td><td style="text-align:right">41</td><td>Tom &amp; Jerry</td></tr>
+<tr><td>Bob</td><td style="text-align:right">99</td><td>Tom &amp; Jerry</td></tr>
+</tbody>
+<tfoot>
+<tr><td colspan="3">2 people</td></tr>
+</tfoot>
+</table>
+"#;
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn key_values() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::key_values(
+        &mut out,
+        col!(Person: .name),
+        col!(Person: .favorite_color),
+    ).max_width(20);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.row(Person { name: "A very long name indeed".into(), ..sample_data().remove(1) })?;
+    s.finish()?;
+
+    let expected = "\
+--------------------
+Cody ........ yellow
+Bob .......... beige
+A very long .. beige
+--------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    // Added columns join the key, and long keys are truncated instead of wrapped:
+    let mut out = Vec::new();
+    let mut s = Stream::key_values(
+        &mut out,
+        col!(Person: .name),
+        col!(Person: .favorite_color),
+    ).max_width(20).wrap(true).leader('_').number_rows();
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.row(Person { name: "A very long name indeed".into(), ..sample_data().remove(1) })?;
+    s.finish()?;
+
+    let expected = "\
+--------------------
+#                   
+--------------------
+1 Cody ______ yellow
+2 Bob ________ beige
+3 A very lon _ beige
+--------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    // A leader alone doesn't make a key/value list:
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name),
+        col!(Person: .favorite_color),
+    ]).leader('.');
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+-------------
+Cody | yellow
+Bob  | beige 
+-------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn json_lines() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("Name"),
+        col!(Person: .age).header("Age"),
+        col!(Person: .text).header("\"Quoted\"").hidden(),
+    ]).format(Format::JsonLines).title("Ignored");
+    for person in sample_data() {
+        s.row(Person { text: "back\\slash\nnewline".into(), ..person })?;
+    }
+    s.footer("Also ignored")?;
+
+    let expected = r#"{"Name":"Cody","Age":"41","\"Quoted\"":"back\\slash\nnewline"}
+{"Name":"Bob","Age":"99","\"Quoted\"":"back\\slash\nnewline"}
+"#;
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+    assert_eq!(Format::JsonLines, "JSON".parse().unwrap());
+
+    // Keys are unique, even for columns without headers:
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("Name"),
+        col!(Person: .age),
+        col!(Person: .favorite_color),
+        col!(Person: .text).header("Name"),
+    ]).format(Format::JsonLines);
+    s.row(Person { text: "hi".into(), ..sample_data().remove(0) })?;
+    s.finish()?;
+
+    let expected = "{\"Name\":\"Cody\",\"Column 2\":\"41\",\"Column 3\":\"yellow\",\"Name 2\":\"hi\"}\n";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn notify_after() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3())
+        .quiet(true)
+        .color(ColorChoice::Always)
+        .notify_after(Duration::ZERO, Notification::Osc9("Done: {rows} rows\x07".into()));
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+    assert_eq!("2 rows\n\x1b]9;Done: 2 rows\x07", String::from_utf8(out).unwrap());
+
+    // Output that isn't a terminal doesn't get escape codes:
+    let mut out = Vec::new();
+    let s = Stream::new(&mut out, cols_3())
+        .quiet(true)
+        .notify_after(Duration::ZERO, Notification::Bell);
+    s.finish()?;
+    assert_eq!("0 rows\n", String::from_utf8(out).unwrap());
+
+    // Fast tables don't deserve a notification:
+    let mut out = Vec::new();
+    let s = Stream::new(&mut out, cols_3())
+        .quiet(true)
+        .color(ColorChoice::Always)
+        .notify_after(Duration::from_secs(60), Notification::Bell);
+    s.finish()?;
+    assert_eq!("0 rows\n", String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn latex() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("Name"),
+        col!(Person: .age).header("Age").right(),
+        col!(Person: .favorite_color).header("Color").center(),
+    ]).format(Format::Latex).title("100% of_people");
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.summary_row(Person {
+        name: "Total & more".into(), age: 140, favorite_color: "~{}".into(), text: "".into(),
+    });
+    s.footer("$5")?;
+
+    let expected = r#"\begin{tabular}{lrc}
+\hline
+\multicolumn{3}{c}{100\% of\_people} \\
+\hline
+Name & Age & Color \\
+\hline
+Cody & 41 & yellow \\
+Bob & 99 & beige \\
+\hline
+Total \& more & 140 & \textasciitilde{}\{\} \\
+\hline
+\multicolumn{3}{c}{\$5} \\
+\hline
+\end{tabular}
+"#;
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn style_by_age() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name),
+        col!(Person: .age),
+    ]).style_by_age(
+        |p: &Person| Duration::from_secs(p.age.into()),
+        &[(Duration::from_secs(60), Style::default().bold()), (Duration::from_secs(90), Style::red())],
+    ).color(ColorChoice::Always);
+    s.row(Person { age: 100, ..sample_data().remove(0) })?;
+    s.row(Person { age: 80, ..sample_data().remove(0) })?;
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+----------
+Cody | 100
+\x1b[38;5;9mCody\x1b[0m | \x1b[38;5;9m 80\x1b[0m
+\x1b[1mCody\x1b[0m | \x1b[1m 41\x1b[0m
+Bob  |  99
+----------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn rst() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3())
+        .format(Format::Rst)
+        .title("People");
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.footer("2 people")?;
+
+    let expected = "\
++-----------------------------+
+|           People            |
++------+-----+----------------+
+| Name | Age | Favorite Color |
++======+=====+================+
+| Cody |  41 | yellow         |
++------+-----+----------------+
+| Bob  |  99 | beige          |
++------+-----+----------------+
+|          2 people           |
++-----------------------------+
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn divided_rules() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).borders(true).divided_rules(true);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.footer("2 people")?;
+
+    let expected = "\
+-------+-----+-----------------
+| Name | Age | Favorite Color |
++------+-----+----------------+
+| Cody |  41 | yellow         |
+| Bob  |  99 | beige          |
++------+-----+----------------+
+|          2 people           |
+-------------------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    // Or turn off the theme's junctions:
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).theme(Theme::unicode()).divided_rules(false);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+───────────────────────────
+Name │ Age │ Favorite Color
+───────────────────────────
+Cody │  41 │ yellow        
+Bob  │  99 │ beige         
+───────────────────────────
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn database_themes() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).theme(Theme::psql());
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.footer("({rows} rows)")?;
+
+    let expected = "\
+Name | Age | Favorite Color
+-----+-----+---------------
+Cody |  41 | yellow        
+Bob  |  99 | beige         
+-----+-----+---------------
+         (2 rows)          
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).theme(Theme::mysql()).borders(true);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
++------+-----+----------------+
+| Name | Age | Favorite Color |
++------+-----+----------------+
+| Cody |  41 | yellow         |
+| Bob  |  99 | beige          |
++------+-----+----------------+
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn frame() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3())
+        .frame(Frame { outer: true, inner_vertical: false, header_rule: false });
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+-------------------------------
+| Name   Age   Favorite Color |
+| Cody    41   yellow         |
+| Bob     99   beige          |
+-------------------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn cell_style() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name),
+        col!(Person: .age).style(|p: &Person| if p.age > 90 { Style::red() } else { Style::default() }),
+    ])
+    .style_by_age(|p: &Person| Duration::from_secs(p.age.into()), &[(Duration::MAX, Style::default().bold())])
+    .color(ColorChoice::Always);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+---------
+\x1b[1mCody\x1b[0m | \x1b[1m41\x1b[0m
+\x1b[1mBob \x1b[0m | \x1b[38;5;9m\x1b[1m99\x1b[0m
+---------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn row_style() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![col!(Person: .name), col!(Person: .age)])
+        .row_style(|index, _| if index % 2 == 1 { Style::default().italic() } else { Style::default() })
+        .color(ColorChoice::Always);
+    for person in sample_data().into_iter().chain(sample_data()) {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+---------
+Cody | 41
+\x1b[3mBob \x1b[0m | \x1b[3m99\x1b[0m
+Cody | 41
+\x1b[3mBob \x1b[0m | \x1b[3m99\x1b[0m
+---------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn title_in_border() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3())
+        .borders(true)
+        .theme(Theme::unicode())
+        .title("People")
+        .title_in_border(true);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+┌─┤ People ├─┬────────────────┐
+│ Name │ Age │ Favorite Color │
+├──────┼─────┼────────────────┤
+│ Cody │  41 │ yellow         │
+│ Bob  │  99 │ beige          │
+└──────┴─────┴────────────────┘
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn quantize_widths() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).quantize_widths(4).max_width(80);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+------------------------------
+Name | Age  |  Favorite Color 
+------------------------------
+Cody |   41 | yellow          
+Bob  |   99 | beige           
+------------------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    // Rounding is skipped if the table wouldn't fit:
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).quantize_widths(10).max_width(30);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+    assert_eq!("-".repeat(27), String::from_utf8(out).unwrap().lines().next().unwrap());
+
+    // Even if rounding up would overflow:
+    let mut s = Stream::new(Vec::new(), cols_3()).quantize_widths(usize::MAX).max_width(30);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    assert_eq!(27, s.finish_rendered()?.width);
+
+    Ok(())
+}
+
+#[test]
+fn highlight() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).highlight(|name| name.starts_with('B'), Style::default().underline()),
+        col!(Person: .age)
+            .highlight(|age| age.parse::<u8>().is_ok_and(|age| age > 90), Style::red())
+            .highlight(|age| age.ends_with('9'), Style::green().bold()),
+    ]).color(ColorChoice::Always);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+---------
+Cody | 41
+\x1b[4mBob \x1b[0m | \x1b[38;5;9m\x1b[1m99\x1b[0m
+---------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn stream_after_rows() -> io::Result<()> {
+    let out = Shared::default();
+    let mut s = Stream::new(out.clone(), vec![col!(Person: .name)]).stream_after_rows(0).grow(false);
+    s.row(sample_data().remove(1))?;
+    assert_eq!("---\nBob\n", String::from_utf8(out.0.take()).unwrap());
+
+    let out = Shared::default();
+    let mut s = Stream::new(out.clone(), vec![col!(Person: .name)]).stream_after_rows(usize::MAX);
+    for _ in 0..1000 {
+        s.row(sample_data().remove(1))?;
+    }
+    assert!(out.0.borrow().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn color_choice() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![col!(Person: .name).style(|_| Style::red())])
+        .color(ColorChoice::Never);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    assert_eq!("----\nCody\nBob \n----\n", String::from_utf8(out).unwrap());
+
+    // Output to a Vec is never a terminal, even if stdout is:
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![col!(Person: .name).style(|_| Style::red())])
+        .color(ColorChoice::Auto);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    assert_eq!("----\nCody\nBob \n----\n", String::from_utf8(out).unwrap());
+
+    // But Stream::stdout checks whether stdout is a terminal:
+    let s = Stream::stdout(cols_3());
+    assert_eq!(io::IsTerminal::is_terminal(&io::stdout()), s.terminal);
+    assert_eq!(ColorChoice::Auto.enabled(s.terminal), s.colors);
+
+    Ok(())
+}
+
+#[test]
+fn interim_footer() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).grow(false);
+    let mut people = sample_data().into_iter();
+    s.row(people.next().unwrap())?;
+    s.interim_footer("{rows} so far")?;
+    s.row(people.next().unwrap())?;
+    s.footer("{rows} rows")?;
+
+    let expected = "\
+---------------------------
+Name | Age | Favorite Color
+---------------------------
+Cody |  41 | yellow        
+---------------------------
+         1 so far          
+---------------------------
+---------------------------
+Name | Age | Favorite Color
+---------------------------
+Bob  |  99 | beige         
+---------------------------
+          2 rows           
+---------------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn alignment_pad() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).grow(false);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    let summary = s.finish()?;
+    assert_eq!(27, summary.width);
+
+    let status = Alignment::Right.pad("2 rows", summary.width);
+    assert_eq!(summary.width, crate::width::str_width(&status));
+    assert_eq!(format!("{:>27}", "2 rows"), status);
+
+    assert_eq!("␊ab ", Alignment::Left.pad("\nab", 4));
+    assert_eq!(" \x1b[1m日\x1b[0m", Alignment::Right.pad("\x1b[1m日本\x1b[0m", 3));
+
+    Ok(())
+}
+
+#[test]
+fn header_case() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("name"),
+        col!(Person: .favorite_color).header("favorite color"),
+    ]).header_case(HeaderCase::Upper).grow(false);
+    s.add_column(col!(Person: .age).header("Age"))?;
+    s.row(sample_data().remove(0))?;
+    s.finish()?;
+
+    let expected = "\
+---------------------------
+NAME | FAVORITE COLOR | AGE
+---------------------------
+Cody | yellow         |  41
+---------------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![col!(Person: .name).header("request URL")])
+        .header_case(HeaderCase::Title)
+        .format(Format::Csv);
+    s.row(sample_data().remove(0))?;
+    s.finish()?;
+    assert_eq!("Request URL\nCody\n", String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn column_max_width() -> io::Result<()> {
+    let render = |grow: bool| -> io::Result<String> {
+        let mut out = Vec::new();
+        let mut s = Stream::new(&mut out, vec![
+            col!(Person: .text).header("Text").max_width(12),
+            col!(Person: .name).header("Name"),
+            col!(Person: .favorite_color).header("Favorite Color"),
+        ]).max_width(40).grow(grow);
+        for person in sample_data() {
+            s.row(person)?;
+        }
+        s.finish()?;
+        Ok(String::from_utf8(out).unwrap())
+    };
+
+    let expected = "\
+------------------------------------
+    Text     | Name | Favorite Color
+------------------------------------
+Here's a lon | Cody | yellow        
+lorum ipsum  | Bob  | beige         
+------------------------------------
+";
+    assert_eq!(expected, render(false)?);
+
+    // Extra width goes to the other columns:
+    let expected = "\
+----------------------------------------
+    Text     |  Name  |  Favorite Color 
+----------------------------------------
+Here's a lon | Cody   | yellow          
+lorum ipsum  | Bob    | beige           
+----------------------------------------
+";
+    assert_eq!(expected, render(true)?);
+
+    Ok(())
+}
+
+#[test]
+fn no_buffer() -> io::Result<()> {
+    let out = Shared::default();
+    let mut s = Stream::new(out.clone(), vec![
+        col!(Person: .name).header("Name").fixed_width(6),
+        col!(Person: .text).header("Text").fixed_width(8),
+    ]).no_buffer();
+
+    let mut people = sample_data().into_iter();
+    s.row(people.next().unwrap())?;
+    let expected = "\
+-----------------
+ Name  |   Text  
+-----------------
+Cody   | Here's a
+";
+    assert_eq!(expected, String::from_utf8(out.0.take()).unwrap());
+
+    s.row(people.next().unwrap())?;
+    assert_eq!("Bob    | lorum ip\n", String::from_utf8(out.0.take()).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn match_width_of() -> io::Result<()> {
+    let banner = "\x1b[1m=== People Report ===\x1b[0m\n(all of them)";
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("Name"),
+        col!(Person: .age).header("Age"),
+    ]).match_width_of(banner).grow(false);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+---------------------
+  Name    |    Age   
+---------------------
+Cody      |        41
+Bob       |        99
+---------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn flex() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("Name").flex(0),
+        col!(Person: .age).header("Age"),
+        col!(Person: .favorite_color).header("Color").flex(3),
+    ]).max_width(30).grow(true);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    // 11 extra columns: 1/4 for Age, 3/4 (and the remainder) for Color.
+    let expected = "\
+------------------------------
+Name |  Age  |      Color     
+------------------------------
+Cody |    41 | yellow         
+Bob  |    99 | beige          
+------------------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    // Huge weights still share the extra width:
+    let mut s = Stream::new(Vec::new(), vec![
+        col!(Person: .name).header("Name").flex(usize::MAX),
+        col!(Person: .favorite_color).header("Color").flex(usize::MAX),
+    ]).max_width(30).grow(true);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    let expected = "\
+------------------------------
+    Name     |      Color     
+------------------------------
+Cody         | yellow         
+Bob          | beige          
+------------------------------
+";
+    assert_eq!(expected, s.finish_rendered()?.text);
+
+    Ok(())
+}
+
+#[test]
+fn status_column() -> io::Result<()> {
+    let status = |p: &Person| match p.age {
+        0..=40 => Status::Ok,
+        41..=90 => Status::Warn,
+        _ => Status::Fail,
+    };
+    let render = |format: StatusFormat| -> io::Result<String> {
+        let mut out = Vec::new();
+        let mut s = Stream::new(&mut out, vec![
+            Column::typed(status, format).header("OK"),
+            col!(Person: .name).header("Name"),
+        ]);
+        for person in sample_data() {
+            s.row(person)?;
+        }
+        s.finish()?;
+        Ok(String::from_utf8(out).unwrap())
+    };
+
+    let expected = "\
+---------
+OK | Name
+---------
+⚠  | Cody
+❌ | Bob 
+---------
+";
+    assert_eq!(expected, render(StatusFormat::emoji())?);
+
+    let expected = "\
+-----------
+ OK  | Name
+-----------
+warn | Cody
+fail | Bob 
+-----------
+";
+    assert_eq!(expected, render(StatusFormat::text())?);
+
+    Ok(())
+}
+
+#[test]
+fn fill_background() -> io::Result<()> {
+    let render = |fill: bool| -> io::Result<String> {
+        let mut out = Vec::new();
+        let mut s = Stream::new(&mut out, vec![col!(Person: .name).right(), col!(Person: .favorite_color)])
+            .row_style(|_, _| Style::default().bg(Color::Blue))
+            .color(ColorChoice::Always)
+            .fill_background(fill);
+        for person in sample_data() {
+            s.row(person)?;
+        }
+        s.finish()?;
+        Ok(String::from_utf8(out).unwrap())
+    };
+
+    let expected = "\
+-------------
+\x1b[48;5;12mCody\x1b[0m | \x1b[48;5;12myellow\x1b[0m
+\x1b[48;5;12m Bob\x1b[0m | \x1b[48;5;12mbeige \x1b[0m
+-------------
+";
+    assert_eq!(expected, render(true)?);
+
+    let expected = "\
+-------------
+\x1b[48;5;12mCody\x1b[0m | \x1b[48;5;12myellow\x1b[0m
+ \x1b[48;5;12mBob\x1b[0m | \x1b[48;5;12mbeige\x1b[0m 
+-------------
+";
+    assert_eq!(expected, render(false)?);
+
+    Ok(())
+}
+
+#[test]
+fn width_pct() -> io::Result<()> {
+    let render = |max_width: usize| -> io::Result<String> {
+        let mut out = Vec::new();
+        let mut s = Stream::new(&mut out, vec![
+            col!(Person: .name).header("Name"),
+            col!(Person: .text).header("Text").width_pct(50.0),
+        ]).max_width(max_width).grow(true);
+        for person in sample_data() {
+            s.row(person)?;
+        }
+        s.finish()?;
+        Ok(String::from_utf8(out).unwrap())
+    };
+
+    let expected = "\
+-----------------------
+   Name    |    Text   
+-----------------------
+Cody       | Here's a l
+Bob        | lorum ipsu
+-----------------------
+";
+    assert_eq!(expected, render(23)?);
+
+    let expected = "\
+-------------------------------
+     Name      |      Text     
+-------------------------------
+Cody           | Here's a long 
+Bob            | lorum ipsum do
+-------------------------------
+";
+    assert_eq!(expected, render(31)?);
+
+    Ok(())
+}
+
+#[test]
+fn priority() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("Name"),
+        col!(Person: .age).header("Age").priority(2),
+        col!(Person: .favorite_color).header("Favorite Color").priority(1),
+        col!(Person: .text).header("Text").priority(3),
+    ]).max_width(20).note_dropped_columns(true);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+--------------------
+Name | Age |  Text  
+--------------------
+Cody |  41 | Here's 
+Bob  |  99 | lorum i
+--------------------
+Hidden to fit: Favorite Color
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn order_by_width() -> io::Result<()> {
+    let truncated = Rc::new(RefCell::new(vec![]));
+    let seen = truncated.clone();
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .text).header("Text"),
+        col!(Person: .favorite_color).header("Color"),
+        col!(Person: .name).header("Name").keep_position(),
+        col!(Person: .age).header("Age"),
+    ]).max_width(40).order_by_width(true)
+        .on_truncate(move |index, _| seen.borrow_mut().push(index));
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    let summary = s.finish()?;
+
+    // Columns are still referred to in the order they were given:
+    assert_eq!(vec![0, 0], *truncated.borrow());
+    assert_eq!("Text", summary.columns[0].name);
+
+    let expected = "\
+----------------------------------------
+Age | Color  | Name |        Text       
+----------------------------------------
+ 41 | yellow | Cody | Here's a long stri
+ 99 | beige  | Bob  | lorum ipsum dolor 
+----------------------------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn hide_if_empty() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("Name"),
+        col!(Person: .text).header("Text").hide_if_empty(),
+        col!(Person: .favorite_color).header("Color").hide_if_empty(),
+    ]);
+    for mut person in sample_data() {
+        person.text.clear();
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+-------------
+Name | Color 
+-------------
+Cody | yellow
+Bob  | beige 
+-------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn column_stats() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Person: .name).header("Name"),
+        col!(Person: .text),
+    ]).max_width(20);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    let summary = s.finish()?;
+
+    let longest_text = sample_data().iter().map(|p| p.text.len()).max().unwrap();
+    assert_eq!(summary.columns, vec![
+        ColumnStats { name: "Name".into(), width: 4, truncated_cells: 0, max_overflow: 0 },
+        ColumnStats { name: "Column 2".into(), width: 13, truncated_cells: 2, max_overflow: longest_text - 13 },
+    ]);
+    assert_eq!(2, summary.truncated_cells);
+
+    Ok(())
+}
+
+#[test]
+fn line_stream() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut table = LineStream::new(&mut out, Split::Whitespace).header_line(true).max_fields(3);
+    // Lines may be split across writes:
+    table.write_all(b"PID  TTY  CMD\r\n  1  ?    init\n4")?;
+    table.write_all(b"2  pts/0  sh -c 'echo hi'\n99")?;
+    table.finish()?;
+
+    let expected = "\
+-----------------------------
+PID |  TTY  |       CMD      
+-----------------------------
+  1 | ?     | init           
+ 42 | pts/0 | sh -c 'echo hi'
+ 99 |       |                
+-----------------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    let mut out = Vec::new();
+    let mut table = LineStream::new(&mut out, Split::Delimiter(",".into())).headers(&["A"]);
+    table.write_all(b"1,2\n3\n")?;
+    table.finish()?;
+
+    let expected = "\
+-----
+A |  
+-----
+1 | 2
+3 |  
+-----
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn line_stream_write_error() -> io::Result<()> {
+    // Fails to write any row that contains a "b":
+    struct NoB(Vec<u8>);
+    impl Write for NoB {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if buf.contains(&b'b') {
+                return Err(io::Error::other("no b's"));
+            }
+            self.0.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    }
+
+    let mut out = NoB(Vec::new());
+    let mut table = LineStream::new(&mut out, Split::Whitespace).configure(|s| s.no_buffer().grow(false));
+    // Only the lines before the failure are consumed:
+    table.write_all(b"x")?;
+    assert_eq!(2, table.write(b"a\nb\nc\n")?);
+    let error = table.write(b"b\nc\n").unwrap_err();
+    assert_eq!("no b's", error.to_string());
+    // The start of a failed line is kept for a retry:
+    table.write_all(b"c\ny")?;
+    assert!(table.write(b"b\n").is_err());
+    table.write_all(b"z\n")?;
+    table.finish()?;
+
+    assert_eq!("--\nxa\nc \nyz\n--\n", String::from_utf8(out.0).unwrap());
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn line_stream_run() -> io::Result<()> {
+    let mut out = Vec::new();
+    let table = LineStream::new(&mut out, Split::Whitespace).header_line(true);
+    let mut command = std::process::Command::new("sh");
+    command.args(["-c", "echo Name Age; echo Cody 41; echo Bob 99; exit 3"]);
+    let (summary, status) = table.run(&mut command)?;
+
+    assert_eq!(2, summary.rows);
+    assert_eq!(Some(3), status.code());
+    let expected = "\
+----------
+Name | Age
+----------
+Cody |  41
+Bob  |  99
+----------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn line_stream_run_write_error() {
+    struct Broken;
+    impl Write for Broken {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken"))
+        }
+        fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    }
+
+    // `yes` never exits on its own, so this only returns if run() stops it:
+    let table = LineStream::new(Broken, Split::Whitespace);
+    let mut command = std::process::Command::new("yes");
+    let err = table.run(&mut command).unwrap_err();
+    assert_eq!(io::ErrorKind::BrokenPipe, err.kind());
+}
+
+#[test]
+fn truncate_policy() -> io::Result<()> {
+    let render = |truncate: Truncate| -> io::Result<String> {
+        let mut out = Vec::new();
+        let mut s = Stream::new(&mut out, vec![
+            Column::new(|f, path: &&str| write!(f, "{}", path)).truncate(truncate),
+        ]).max_width(10).grow(false);
+        s.row("src/tablestream/lib.rs")?;
+        s.row("日本語/ファイル")?;
+        s.finish()?;
+        Ok(String::from_utf8(out).unwrap())
+    };
+
+    assert_eq!("----------\nsrc/tables\n日本語/フ \n----------\n", render(Truncate::End)?);
+    assert_eq!("----------\neam/lib.rs\n/ファイル \n----------\n", render(Truncate::Start)?);
+    assert_eq!("----------\nsrc/tib.rs\n日本ァイル\n----------\n", render(Truncate::Middle)?);
+
+    Ok(())
+}
+
+#[test]
+fn mark_truncated() -> io::Result<()> {
+    let render = |truncate: Truncate| -> io::Result<String> {
+        let mut out = Vec::new();
+        let mut s = Stream::new(&mut out, vec![
+            Column::new(|f, path: &&str| write!(f, "{}", path)).truncate(truncate),
+        ]).max_width(10).grow(false).mark_truncated(true);
+        s.row("src/tablestream/lib.rs")?;
+        s.row("short")?;
+        s.finish()?;
+        Ok(String::from_utf8(out).unwrap())
+    };
+
+    assert_eq!("----------\nsrc/table…\nshort     \n----------\n", render(Truncate::End)?);
+    assert_eq!("----------\n…am/lib.rs\nshort     \n----------\n", render(Truncate::Start)?);
+    assert_eq!("----------\nsrc/t…b.rs\nshort     \n----------\n", render(Truncate::Middle)?);
+
+    Ok(())
+}
+
+#[test]
+fn combining_marks() -> io::Result<()> {
+    use crate::width::{str_width, truncate, truncate_start};
+
+    // Composed and decomposed forms take the same width:
+    assert_eq!(str_width("Tiếng Việt"), str_width("Tie\u{302}\u{301}ng Vie\u{323}\u{302}t"));
+    assert_eq!(2, str_width("कि"));
+
+    // Marks stay with their character:
+    assert_eq!(("Tie\u{302}\u{301}", 3), truncate("Tie\u{302}\u{301}ng", 3));
+    assert_eq!(("x", 1), truncate_start("e\u{301}x", 1));
+    assert_eq!(("ष", 1), truncate_start("क\u{94d}ष", 1));
+    assert_eq!(("👨", 2), truncate("👨\u{200d}👩\u{200d}👧", 2));
+
+    // A variation selector can make a character wider:
+    assert_eq!(2, str_width("🕯\u{fe0f}"));
+    assert_eq!(("", 0), truncate("🕯\u{fe0f}x", 1));
+    assert_eq!(("🕯\u{fe0f}", 2), truncate("🕯\u{fe0f}x", 2));
+    assert_eq!(("", 0), truncate_start("x🕯\u{fe0f}", 1));
+    assert_eq!(("🕯\u{fe0f}", 2), truncate_start("x🕯\u{fe0f}", 2));
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        Column::new(|f, word: &&str| write!(f, "{}", word)).header("Word"),
+        Column::new(|f, _: &&str| write!(f, "x")).header("X"),
+    ]).grow(false);
+    s.row("Tiếng Việt")?;
+    // Decomposed values are written composed:
+    s.row("Tie\u{302}\u{301}ng Vie\u{323}\u{302}t")?;
+    s.row("नमस्ते")?;
+    s.finish()?;
+
+    let expected = "\
+--------------
+   Word    | X
+--------------
+Tiếng Việt | x
+Tiếng Việt | x
+नमस्ते       | x
+--------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn legend() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        Column::new(|f, (name, _): &(&str, &str)| write!(f, "{}", name)).header("Check"),
+        Column::new(|f, (_, status): &(&str, &str)| write!(f, "{}", status)).header("Status"),
+    ]).max_width(24).grow(false).legend(&[
+        ("+", "passed"),
+        ("~", "flaky"),
+        ("x", "failed"),
+        ("?", "not run, because an earlier step failed"),
+    ]);
+    s.row(("build", "+"))?;
+    s.row(("unit tests", "~"))?;
+    s.finish()?;
+
+    let expected = "\
+-------------------
+  Check    | Status
+-------------------
+build      | +     
+unit tests | ~     
+-------------------
++ passed   ~ flaky
+x failed
+? not run, because
+an earlier step
+failed
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn on_truncate() -> io::Result<()> {
+    let truncated = Rc::new(RefCell::new(vec![]));
+    let seen = truncated.clone();
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        Column::new(|f, _: &(&str, &str)| write!(f, "x")),
+        Column::new(|f, (path, _): &(&str, &str)| write!(f, "{}", path)),
+        Column::new(|f, (_, note): &(&str, &str)| write!(f, "{}", note)),
+    ]).max_width(16).grow(false)
+        .on_truncate(move |column, width| seen.borrow_mut().push((column, width)));
+    s.row(("src/lib.rs", "ok"))?;
+    s.row(("src/tablestream/lib.rs", "ok"))?;
+    s.row(("src/a.rs", "needs review"))?;
+    let summary = s.finish()?;
+
+    assert_eq!(vec![(1, 10), (1, 22), (1, 8), (2, 12)], *truncated.borrow());
+    assert_eq!(4, summary.truncated_cells);
+
+    Ok(())
+}
+
+#[test]
+fn rollup() -> io::Result<()> {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    struct Request { time: SystemTime, path: &'static str, bytes: u32 }
+    let at = |seconds: u64, path, bytes| Request { time: UNIX_EPOCH + Duration::from_secs(seconds), path, bytes };
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        col!(Request: .path).header("Path"),
+        col!(Request: .bytes).header("Bytes"),
+    ]).max_width(40).grow(true)
+        .rollup(Duration::from_secs(60), |r: &Request| r.time)
+        .rollup_sum("bytes", |r: &Request| r.bytes.into());
+    s.row(at(3600, "/", 100))?;
+    s.row(at(3630, "/about", 250))?;
+    // Out of order, so counted with the previous row:
+    s.row(at(3590, "/", 100))?;
+    s.row(at(3725, "/", 150))?;
+    s.finish()?;
+
+    let expected = "\
+----------------------------------------
+       Path         |       Bytes       
+----------------------------------------
+/                   |                100
+/about              |                250
+/                   |                100
+01:00:00–01:01:00: 3 rows, bytes: 450   
+/                   |                150
+01:02:00–01:03:00: 1 row, bytes: 150    
+----------------------------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn degrade_grow_and_error() -> io::Result<()> {
+    let render = |degrade: Degrade, max_width: usize| -> io::Result<String> {
+        let mut out = Vec::new();
+        let mut s = Stream::new(&mut out, vec![
+            Column::new(|f, (hash, _): &(&str, &str)| write!(f, "{}", hash)).degrade(&[degrade]),
+            Column::new(|f, (_, message): &(&str, &str)| write!(f, "{}", message)),
+        ]).max_width(max_width).grow(false);
+        s.row(("3f2a9c1e5b7d", "Fix the flux capacitor"))?;
+        s.row(("8be0d4f6a2c3", "Release"))?;
+        s.finish()?;
+        Ok(String::from_utf8(out).unwrap())
+    };
+
+    // Other columns are squeezed first:
+    let expected = "\
+--------------------
+3f2a9c1e5b7d | Fix t
+8be0d4f6a2c3 | Relea
+--------------------
+";
+    assert_eq!(expected, render(Degrade::Grow, 20)?);
+    assert_eq!(expected, render(Degrade::Error, 20)?);
+
+    // Then the table grows, rather than cutting off hashes:
+    let expected = "\
+----------------
+3f2a9c1e5b7d | F
+8be0d4f6a2c3 | R
+----------------
+";
+    assert_eq!(expected, render(Degrade::Grow, 10)?);
+
+    let error = render(Degrade::Error, 10).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, error.kind());
+
+    // Only the row that doesn't fit is lost:
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        Column::new(|f, id: &&str| write!(f, "{}", id)).header("ID").min_width(4).degrade(&[Degrade::Error]),
+    ]).max_width(4).grow(false);
+    s.row("a1")?;
+    s.row("3f2a9c1e5b7d")?;
+    s.row("b2")?;
+    let error = s.flush().unwrap_err();
+    assert_eq!("row 2, ID: a value is 12 columns wide, but the column is only 4 columns wide", error.to_string());
+    s.row("c3")?;
+    s.finish()?;
+
+    let expected = "\
+----
+ ID 
+----
+a1  
+b2  
+c3  
+----
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn chapter_by() -> io::Result<()> {
+    let render = |title: Option<&str>, stream_after: usize| -> io::Result<String> {
+        let mut out = Vec::new();
+        let mut s = Stream::new(&mut out, vec![
+            Column::new(|f, (_, item, _): &(&str, &str, u32)| write!(f, "{}", item)).header("Item"),
+            Column::new(|f, (_, _, total): &(&str, &str, u32)| write!(f, "{}", total)).header("Total").right(),
+        ]).chapter_by(|(region, _, _): &(&str, &str, u32)| region.to_string())
+            .stream_after_rows(stream_after).grow(false);
+        if let Some(title) = title {
+            s = s.title(title);
+        }
+        s.row(("East", "Widgets", 120))?;
+        s.row(("East", "Gadgets", 45))?;
+        s.row(("West", "Widgets", 80))?;
+        s.finish()?;
+        Ok(String::from_utf8(out).unwrap())
+    };
+
+    let expected = "\
+---------------
+     East      
+---------------
+ Item   | Total
+---------------
+Widgets |   120
+Gadgets |    45
+---------------
+---------------
+     West      
+---------------
+ Item   | Total
+---------------
+Widgets |    80
+---------------
+";
+    assert_eq!(expected, render(None, 100)?);
+    // Same layout, whether or not chapters start while rows are buffered:
+    assert_eq!(expected, render(None, 0)?);
+
+    let titled = render(Some("Sales: {chapter}"), 100)?;
+    assert!(titled.contains(" Sales: East "), "{}", titled);
+    assert!(titled.contains(" Sales: West "), "{}", titled);
+
+    Ok(())
+}
+
+#[test]
+fn numbers_align_right() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        Column::new(|f, (size, _, _): &(&str, &str, &str)| write!(f, "{}", size)).header("Size"),
+        Column::new(|f, (_, used, _): &(&str, &str, &str)| write!(f, "{}", used)).header("Used").left(),
+        Column::new(|f, (_, _, name): &(&str, &str, &str)| write!(f, "{}", name)).header("Name"),
+    ]).grow(false);
+    s.row(("1,024", "50%", "a"))?;
+    s.row(("-3.5", "7%", "42"))?;
+    s.row(("", "", "b"))?;
+    s.finish()?;
+
+    let expected = "\
+-------------------
+Size  | Used | Name
+-------------------
+1,024 | 50%  | a   
+ -3.5 | 7%   | 42  
+      |      | b   
+-------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn number_rows() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).number_rows().grow(false).max_height(2);
+    s.pin(Person { name: "Pinned".into(), ..sample_data().remove(0) }, Position::Top)?;
+    for person in sample_data().into_iter().chain(sample_data()) {
+        s.row(person)?;
+    }
+    let (_, rows) = s.finish_numbered()?;
+
+    let expected = "\
+---------------------------------
+# |  Name  | Age | Favorite Color
+---------------------------------
+  | Pinned |  41 | yellow        
+---------------------------------
+1 | Cody   |  41 | yellow        
+2 | Bob    |  99 | beige         
+… (2 more)                       
+---------------------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+    let names: Vec<_> = rows.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(vec!["Cody", "Bob"], names);
+
+    Ok(())
+}
+
+#[test]
+fn percent_of_total() -> io::Result<()> {
+    let render = |stream_after: usize| -> io::Result<String> {
+        let mut out = Vec::new();
+        let mut s = Stream::new(&mut out, vec![
+            Column::new(|f, (path, _): &(&str, u32)| write!(f, "{}", path)).header("Path"),
+            Column::new(|f, (_, bytes): &(&str, u32)| write!(f, "{}", bytes)).header("Bytes"),
+        ]).percent_of_total("Share", |(_, bytes): &(&str, u32)| (*bytes).into())
+            .stream_after_rows(stream_after).grow(false);
+        s.row(("src", 600))?;
+        s.row(("docs", 300))?;
+        s.row(("tests", 100))?;
+        s.finish()?;
+        Ok(String::from_utf8(out).unwrap())
+    };
+
+    let expected = "\
+---------------------
+Path  | Bytes | Share
+---------------------
+src   |   600 | 60.0%
+docs  |   300 | 30.0%
+tests |   100 | 10.0%
+---------------------
+";
+    assert_eq!(expected, render(100)?);
+
+    // Once streaming, rows only know the total so far:
+    let expected = "\
+---------------------
+Path | Bytes | Share 
+---------------------
+src  |   600 | 100.0%
+docs |   300 |  33.3%
+test |   100 |  10.0%
+---------------------
+";
+    assert_eq!(expected, render(0)?);
+
+    Ok(())
+}
+
+#[test]
+fn thousands() -> io::Result<()> {
+    use crate::{NumberFormat, group_thousands};
+
+    assert_eq!("0", group_thousands("0", ','));
+    assert_eq!("999", group_thousands("999", ','));
+    assert_eq!("1,000", group_thousands("1000", ','));
+    assert_eq!("-24,150,000", group_thousands("-24150000", ','));
+    assert_eq!("1.234.567,5", group_thousands("1234567,5", '.'));
+    assert_eq!("12 345.678901", group_thousands("12345.678901", ' '));
+    assert_eq!("", group_thousands("", ','));
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        Column::typed(|c: &(&str, u32)| c.1, NumberFormat::default().thousands(',')).header("Population"),
+        Column::typed(|c: &(&str, u32)| c.1 as f64 / 1e3, NumberFormat::default().precision(1).thousands('_')).header("Thousands"),
+    ]).grow(false);
+    s.row(("Shanghai", 24_150_000))?;
+    s.row(("Lagos", 950))?;
+    s.finish()?;
+
+    let expected = "\
+----------------------
+Population | Thousands
+----------------------
+24,150,000 |  24_150.0
+       950 |       0.9
+----------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn byte_format() -> io::Result<()> {
+    use crate::{ByteFormat, format_bytes};
+
+    assert_eq!("0 B", format_bytes(0));
+    assert_eq!("1023 B", format_bytes(1023));
+    assert_eq!("1.0 KiB", format_bytes(1024));
+    assert_eq!("1.2 MiB", format_bytes(1_234_567));
+    assert_eq!("16.0 EiB", format_bytes(u64::MAX));
+    // Values that round up to the next unit are shown in it:
+    assert_eq!("1.0 MiB", format_bytes(1024 * 1024 - 1));
+    assert_eq!("1023.9 KiB", format_bytes(1023 * 1024 + 900));
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        Column::typed(|size: &usize| *size, ByteFormat::default()).header("Memory"),
+        Column::typed(|size: &usize| *size, ByteFormat::default().decimal().precision(2)).header("Disk"),
+    ]).grow(false);
+    s.row(1_500_000)?;
+    s.row(999)?;
+    s.finish()?;
+
+    let expected = "\
+-----------------
+Memory  |  Disk  
+-----------------
+1.4 MiB | 1.50 MB
+  999 B |   999 B
+-----------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    // Negative values, like changes in size, keep their sign:
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        Column::typed(|change: &i64| *change, ByteFormat::default()).header("Change"),
+    ]).grow(false);
+    s.row(-2048)?;
+    s.row(-10)?;
+    s.row(1536)?;
+    s.finish()?;
+
+    let expected = "\
+--------
+ Change 
+--------
+-2.0 KiB
+   -10 B
+ 1.5 KiB
+--------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn sample_only() -> io::Result<()> {
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, cols_3()).no_buffer().grow(false);
+    s.sample_only(&[Person { name: "Maximilian".into(), age: 100, ..sample_data().remove(0) }])?;
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    s.finish()?;
+
+    let expected = "\
+---------------------------------
+   Name    | Age | Favorite Color
+---------------------------------
+Cody       |  41 | yellow        
+Bob        |  99 | beige         
+---------------------------------
+";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn finish_rendered() -> io::Result<()> {
+    let mut s = Stream::new(Vec::new(), cols_3()).max_width(24).grow(false)
+        .legend(&[("Age", "in years, as of the last birthday")]);
+    for person in sample_data() {
+        s.row(person)?;
+    }
+    let rendered = s.finish_rendered()?;
+
+    let expected = "\
+---------------------------
+Name | Age | Favorite Color
+---------------------------
+Cody |  41 | yellow        
+Bob  |  99 | beige         
+---------------------------
+Age in years, as of the
+last birthday
+";
+    assert_eq!(expected, rendered.text);
+    // Headers were too wide for max_width:
+    assert_eq!(27, rendered.width);
+    assert_eq!(8, rendered.lines);
+    assert_eq!(27, rendered.summary.width);
+
+    Ok(())
+}
+
+#[test]
+fn duration_format() -> io::Result<()> {
+    use crate::{DurationFormat, format_duration};
+
+    assert_eq!("0ns", format_duration(Duration::ZERO));
+    assert_eq!("999ns", format_duration(Duration::from_nanos(999)));
+    assert_eq!("1.5µs", format_duration(Duration::from_nanos(1_500)));
+    assert_eq!("12.3ms", format_duration(Duration::from_micros(12_345)));
+    assert_eq!("59.9s", format_duration(Duration::from_millis(59_900)));
+    assert_eq!("1m 0s", format_duration(Duration::from_secs(60)));
+    // Values that round up to the next unit are shown in it:
+    assert_eq!("1.0ms", format_duration(Duration::from_nanos(999_960)));
+    assert_eq!("1.0s", format_duration(Duration::from_micros(999_960)));
+    assert_eq!("1m 0s", format_duration(Duration::from_millis(59_960)));
+    assert_eq!("1h 23m 45s", format_duration(Duration::from_secs(5025)));
+    assert_eq!("2d 1h 0m", format_duration(Duration::from_secs(2 * 86400 + 3600 + 59)));
+
+    let mut out = Vec::new();
+    let mut s = Stream::new(&mut out, vec![
+        Column::new(|f, job: &&str| write!(f, "{}", job)).header("Job"),
+        Column::typed(|job: &&str| Duration::from_millis(job.len() as u64 * 1234), DurationFormat::default().precision(2)).header("Runtime"),
+    ]).grow(false);
+    s.row("build")?;
+    s.row("test everything")?;
+    s.finish()?;
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("build           |   6.17s"), "{}", out);
+    assert!(out.contains("test everything |  18.51s"), "{}", out);
+
+    Ok(())
+}
+
+#[test]
+fn profile_email() -> io::Result<()> {
+    let mut s = Stream::new(Vec::new(), vec![
+        Column::new(|f, (name, _): &(&str, &str)| write!(f, "{}", name)).header("Job")
+            .style(|_| Style::red()),
+        Column::new(|f, (_, note): &(&str, &str)| write!(f, "{}", note)).header("Note"),
+    ]).profile(Profile::Email);
+    s.row(("backup", "ok"))?;
+    s.row(("reindex", "took longer than usual because the search index was rebuilt from scratch after the upgrade"))?;
+    let rendered = s.finish_rendered()?;
+
+    let expected = "\
+------------------------------------------------------------------------
+  Job   |                              Note                             
+------------------------------------------------------------------------
+backup  | ok                                                            
+reindex | took longer than usual because the search index was rebuilt   
+        | from scratch after the upgrade                                
+------------------------------------------------------------------------
+";
+    assert_eq!(expected, rendered.text);
+    assert_eq!(72, rendered.width);
+
+    Ok(())
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn datetime() -> io::Result<()> {
+    use chrono::NaiveDate;
+    use crate::DateTimeFormat;
+
+    assert_eq!(16, DateTimeFormat::new("%Y-%m-%d %H:%M").width());
+    assert_eq!(18, DateTimeFormat::new("%B %-d, %Y").width()); // September 28, 2000
+
+    let mut s = Stream::new(Vec::new(), vec![
+        Column::datetime(|d: &NaiveDate| *d, "%a %b %-d").header("Day"),
+        Column::datetime(|d: &NaiveDate| *d, "%H:%M").header("Time"),
+    ]).grow(false);
+    let day = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+    s.row(day)?;
+    s.finish().expect_err("NaiveDate has no time");
+
+    let mut s = Stream::new(Vec::new(), vec![
+        Column::datetime(|d: &NaiveDate| *d, "%a %b %-d").header("Day"),
+    ]).grow(false);
+    s.row(day)?;
+    let expected = "\
+----------
+   Day    
+----------
+Wed May 1 
+----------
+";
+    assert_eq!(expected, s.finish_rendered()?.text);
+
+    Ok(())
+}
+
+#[test]
+fn sparse_columns() -> io::Result<()> {
+    type Log = (&'static str, &'static str, &'static str);
+    let cols = || vec![
+        Column::new(|f, (msg, _, _): &Log| write!(f, "{}", msg)).header("Message"),
+        Column::new(|f, (_, user, _): &Log| write!(f, "{}", user)).header("User"),
+        Column::new(|f, (_, _, trace): &Log| write!(f, "{}", trace)).header("Trace"),
+    ];
+    let rows: Vec<Log> = vec![
+        ("started", "amy", ""),
+        ("request", "bo", ""),
+        ("failed", "amy", "handler.rs:42 in fetch_all"),
+        ("request", "", ""),
+    ];
+
+    let mut s = Stream::new(Vec::new(), cols()).grow(false)
+        .hide_sparse_columns(0.5)
+        .note_dropped_columns(true);
+    for row in rows.iter() {
+        s.row(*row)?;
+    }
+    let expected = "\
+--------------
+Message | User
+--------------
+started | amy 
+request | bo  
+failed  | amy 
+request |     
+--------------
+Mostly empty: Trace
+";
+    assert_eq!(expected, s.finish_rendered()?.text);
+
+    let mut s = Stream::new(Vec::new(), cols()).grow(false)
+        .shrink_sparse_columns(0.5);
+    for row in rows.iter() {
+        s.row(*row)?;
+    }
+    let expected = "\
+----------------------
+Message | User | Trace
+----------------------
+started | amy  |      
+request | bo   |      
+failed  | amy  | hand…
+request |      |      
+----------------------
+";
+    assert_eq!(expected, s.finish_rendered()?.text);
+
+    Ok(())
+}
+
+#[test]
+fn none_as() -> io::Result<()> {
+    struct Host {
+        name: &'static str,
+        owner: Option<&'static str>,
+        load: Option<f64>,
+    }
+    let hosts = || vec![
+        Host { name: "db1", owner: Some("amy"), load: Some(0.5) },
+        Host { name: "web1", owner: None, load: None },
+        Host { name: "web2", owner: None, load: Some(1.25) },
+    ];
+    let cols = || vec![
+        col!(Host: .name).header("Host"),
+        col!(Host: .owner?).header("Owner").none_as("(none)"),
+        col!(Host: .load?).header("Load"),
+    ];
+
+    let mut s = Stream::new(Vec::new(), cols()).grow(false).none_as("-");
+    for host in hosts() {
+        s.row(host)?;
+    }
+    let expected = "\
+--------------------
+Host | Owner  | Load
+--------------------
+db1  | amy    |  0.5
+web1 | (none) |    -
+web2 | (none) | 1.25
+--------------------
+";
+    assert_eq!(expected, s.finish_rendered()?.text);
+
+    // Exports leave empty values empty:
+    let mut s = Stream::new(Vec::new(), cols()).format(Format::Csv).none_as("-");
+    for host in hosts() {
+        s.row(host)?;
+    }
+    assert_eq!("Host,Owner,Load\ndb1,amy,0.5\nweb1,,\nweb2,,1.25\n", s.finish_rendered()?.text);
+
+    Ok(())
+}
+
+#[test]
+fn boolean_column() -> io::Result<()> {
+    use crate::BoolStyle;
+
+    struct Feature {
+        name: &'static str,
+        enabled: bool,
+    }
+    let features = || vec![
+        Feature { name: "search", enabled: true },
+        Feature { name: "sync", enabled: false },
+    ];
+    let cols = |style| vec![
+        col!(Feature: .name).header("Feature"),
+        Column::boolean(|f: &Feature| f.enabled, style).header("Enabled").center(),
+        Column::typed(|f: &Feature| !f.enabled, BoolStyle::YesNo).header("Beta"),
+    ];
+
+    let mut s = Stream::new(Vec::new(), cols(BoolStyle::CheckCross)).grow(false);
+    for feature in features() {
+        s.row(feature)?;
+    }
+    let expected = "\
+------------------------
+Feature | Enabled | Beta
+------------------------
+search  |    ✓    | no  
+sync    |    ✗    | yes 
+------------------------
+";
+    assert_eq!(expected, s.finish_rendered()?.text);
+
+    let mut s = Stream::new(Vec::new(), cols(BoolStyle::Checkbox)).grow(false);
+    for feature in features() {
+        s.row(feature)?;
+    }
+    let expected = "\
+------------------------
+Feature | Enabled | Beta
+------------------------
+search  |   [x]   | no  
+sync    |   [ ]   | yes 
+------------------------
+";
+    assert_eq!(expected, s.finish_rendered()?.text);
+
+    let mut s = Stream::new(Vec::new(), cols(BoolStyle::CheckCross)).format(Format::Csv);
+    for feature in features() {
+        s.row(feature)?;
+    }
+    assert_eq!("Feature,Enabled,Beta\nsearch,true,no\nsync,false,yes\n", s.finish_rendered()?.text);
+
+    Ok(())
+}
+
+#[test]
+fn hex_format() -> io::Result<()> {
+    use crate::{HexFormat, format_hex};
+
+    assert_eq!("", format_hex(&[]));
+    assert_eq!("de ad be ef", format_hex(&[0xde, 0xad, 0xbe, 0xef]));
+    let bytes: Vec<u8> = (0..12).collect();
+    assert_eq!("00 01 02 03 04 05 06 07 …", format_hex(&bytes));
+
+    struct Packet {
+        kind: &'static str,
+        payload: Vec<u8>,
+    }
+    let mut s = Stream::new(Vec::new(), vec![
+        col!(Packet: .kind).header("Kind"),
+        Column::hex(|p: &Packet| &p.payload, HexFormat::default().max_bytes(6).group(2)).header("Payload"),
+    ]).grow(false);
+    s.row(Packet { kind: "ping", payload: vec![0xca, 0xfe] })?;
+    s.row(Packet { kind: "data", payload: bytes.clone() })?;
+    s.row(Packet { kind: "empty", payload: vec![] })?;
+    let expected = "\
+------------------------
+Kind  |     Payload     
+------------------------
+ping  | cafe            
+data  | 0001 0203 0405 …
+empty |                 
+------------------------
+";
+    assert_eq!(expected, s.finish_rendered()?.text);
+
+    Ok(())
+}
+
+#[test]
+fn footer_row() -> io::Result<()> {
+    use crate::{ByteFormat, DurationFormat, NumberFormat};
+
+    let sales = [("Tea", 3.5, 2), ("Cake", 4.25, 1), ("Scone", 2.0, 4)];
+    let cols = || vec![
+        Column::new(|f, (item, _, _): &(&str, f64, u32)| write!(f, "{}", item)).header("Item")
+            .footer_text("Total"),
+        Column::typed(|(_, price, _): &(&str, f64, u32)| *price, NumberFormat::default().precision(2))
+            .header("Price").footer_avg(),
+        Column::typed(|(_, _, qty): &(&str, f64, u32)| *qty, NumberFormat::default()).header("Qty")
+            .footer_sum(),
+    ];
+
+    let mut s = Stream::new(Vec::new(), cols()).grow(false).footer_row();
+    for sale in sales {
+        s.row(sale)?;
+    }
+    let expected = "\
+-------------------
+Item  | Price | Qty
+-------------------
+Tea   |  3.50 |   2
+Cake  |  4.25 |   1
+Scone |  2.00 |   4
+-------------------
+Total |  3.25 |   7
+-------------------
+";
+    assert_eq!(expected, s.finish_rendered()?.text);
+
+    // Totals include rows that were streamed before the footer was measured, and rows that
+    // were hidden:
+    let mut s = Stream::new(Vec::new(), cols()).grow(false).no_buffer().max_height(2).footer_row();
+    for sale in sales {
+        s.row(sale)?;
+    }
+    let expected = "\
+-------------------
+Item  | Price | Qty
+-------------------
+Tea   |  3.50 |   2
+Cake  |  4.25 |   1
+… (1 more)         
+-------------------
+Total |  3.25 |   7
+-------------------
+";
+    assert_eq!(expected, s.finish_rendered()?.text);
+
+    // Totals are of the values, not the rounded text, and are displayed in the column's format:
+    let mut s = Stream::new(Vec::new(), vec![
+        Column::typed(|(size, _, _): &(u64, Duration, f64)| *size, ByteFormat::default()).header("Size")
+            .footer_sum(),
+        Column::typed(|(_, time, _): &(u64, Duration, f64)| *time, DurationFormat::default()).header("Time")
+            .footer_sum(),
+        Column::typed(|(_, _, rate): &(u64, Duration, f64)| *rate, NumberFormat::default().precision(1))
+            .header("Rate").footer_sum(),
+        Column::new(|f, (_, _, rate): &(u64, Duration, f64)| write!(f, "{:.1}", rate)).header("Untyped")
+            .footer_sum(),
+    ]).grow(false).footer_row();
+    for _ in 0..3 {
+        s.row((700, Duration::from_secs(25), 0.04))?;
+    }
+    let expected = "\
+---------------------------------
+ Size   |  Time  | Rate | Untyped
+---------------------------------
+  700 B |  25.0s |  0.0 |     0.0
+  700 B |  25.0s |  0.0 |     0.0
+  700 B |  25.0s |  0.0 |     0.0
+---------------------------------
+2.1 KiB | 1m 15s |  0.1 |        
+---------------------------------
+";
+    assert_eq!(expected, s.finish_rendered()?.text);
+
+    // Footers are only displayed in tables:
+    let mut s = Stream::new(Vec::new(), cols()).format(Format::Csv).footer_row();
+    for sale in sales {
+        s.row(sale)?;
+    }
+    assert_eq!("Item,Price,Qty\nTea,3.50,2\nCake,4.25,1\nScone,2.00,4\n", s.finish_rendered()?.text);
+
+    Ok(())
+}
+
+#[test]
+fn footer_count() -> io::Result<()> {
+    use crate::NumberFormat;
+
+    let mut s = Stream::new(Vec::new(), vec![
+        Column::new(|f, (city, _): &(&str, u32)| write!(f, "{}", city)).header("City")
+            .footer_count(),
+        Column::typed(|(_, pop): &(&str, u32)| *pop, NumberFormat::default().thousands(','))
+            .header("Population").footer_sum(),
+    ]).grow(false).footer_row();
+    s.row(("Tokyo", 37_400_068))?;
+    s.row(("Delhi", 28_514_000))?;
+    s.row(("Shanghai", 25_582_000))?;
+    let expected = "\
+---------------------
+  City   | Population
+---------------------
+Tokyo    | 37,400,068
+Delhi    | 28,514,000
+Shanghai | 25,582,000
+---------------------
+3 rows   | 91,496,068
+---------------------
+";
+    assert_eq!(expected, s.finish_rendered()?.text);
+
+    let mut s = Stream::new(Vec::new(), vec![
+        Column::new(|f, word: &&str| write!(f, "{}", word.len())).header("Len").footer_count(),
+    ]).grow(false).footer_row();
+    s.row("one")?;
+    let expected = "\
+-----
+ Len 
+-----
+    3
+-----
+1 row
+-----
+";
+    assert_eq!(expected, s.finish_rendered()?.text);
+
+    Ok(())
+}
+
+#[test]
+fn count_rows() -> io::Result<()> {
+    let render = |people: Vec<Person>, format: Format| -> io::Result<String> {
+        let mut s = Stream::new(Vec::new(), cols_3()).grow(false).count_rows().format(format);
+        for person in people {
+            s.row(person)?;
+        }
+        Ok(s.finish_rendered()?.text)
+    };
+
+    let expected = "\
+---------------------------
+Name | Age | Favorite Color
+---------------------------
+Cody |  41 | yellow        
+Bob  |  99 | beige         
+---------------------------
+          2 rows           
+---------------------------
+";
+    assert_eq!(expected, render(sample_data(), Format::Table)?);
+
+    let mut one = sample_data();
+    one.truncate(1);
+    assert!(render(one, Format::Table)?.contains("1 row "));
+
+    assert_eq!("Name,Age,Favorite Color\nCody,41,yellow\nBob,99,beige\n", render(sample_data(), Format::Csv)?);
+
+    Ok(())
+}
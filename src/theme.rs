@@ -0,0 +1,321 @@
+//! The characters used to draw a table. See: [`Stream::theme`](crate::Stream::theme).
+
+use crate::width::{printable, str_width, truncate};
+
+/// The characters used to draw a table's rules, borders, and dividers.
+///
+/// ```
+/// # use tablestream::Theme;
+/// // Double lines all around:
+/// let theme = Theme::default()
+///     .rule('═')
+///     .header_rule('═')
+///     .border('║')
+///     .divider("║")
+///     .corners(['╔', '╗', '╚', '╝'])
+///     .junctions(['╦', '╠', '╬', '╣', '╩']);
+/// ```
+///
+/// Characters should each be one column wide.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    rule: char,
+    header_rule: char,
+    border: char,
+    divider: String,
+    corners: Option<[char; 4]>,
+    junctions: Option<[char; 5]>,
+    fill: char,
+    outer_rules: bool,
+}
+
+impl Default for Theme {
+    /// The default ASCII theme. (ex: `-`, `|`)
+    fn default() -> Self {
+        Self {
+            rule: '-',
+            header_rule: '-',
+            border: '|',
+            divider: "|".to_string(),
+            corners: None,
+            junctions: None,
+            fill: ' ',
+            outer_rules: true,
+        }
+    }
+}
+
+impl Theme {
+    /// A theme drawn with Unicode box-drawing characters. (ex: `─`, `│`, `┌`)
+    pub fn unicode() -> Self {
+        Self {
+            rule: '─',
+            header_rule: '─',
+            border: '│',
+            divider: "│".to_string(),
+            corners: Some(['┌', '┐', '└', '┘']),
+            junctions: Some(['┬', '├', '┼', '┤', '┴']),
+            fill: ' ',
+            outer_rules: true,
+        }
+    }
+
+    /// Looks like the PostgreSQL client, `psql`: `Name | Age`, with a `-----+----` header rule,
+    /// and no rules above or below the table.
+    pub fn psql() -> Self {
+        Self {
+            outer_rules: false,
+            junctions: Some(['+'; 5]),
+            ..Self::default()
+        }
+    }
+
+    /// Looks like the MySQL client: `+------+-----+` boxes. Use with [`Stream::borders`].
+    ///
+    /// [`Stream::borders`]: crate::Stream::borders
+    pub fn mysql() -> Self {
+        Self {
+            corners: Some(['+'; 4]),
+            junctions: Some(['+'; 5]),
+            ..Self::default()
+        }
+    }
+
+    /// The grid of a reStructuredText grid table. See: [`Format::Rst`](crate::Format::Rst).
+    pub(crate) fn rst() -> Self {
+        Self {
+            rule: '-',
+            header_rule: '=',
+            border: '|',
+            divider: "|".to_string(),
+            corners: Some(['+'; 4]),
+            junctions: Some(['+'; 5]),
+            fill: ' ',
+            outer_rules: true,
+        }
+    }
+
+    /// The character used to draw horizontal rules. (default: `-`)
+    pub fn rule(mut self, rule: char) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    /// The character used to draw the rule that separates headers from rows. (default: `-`)
+    pub fn header_rule(mut self, rule: char) -> Self {
+        self.header_rule = rule;
+        self
+    }
+
+    /// The character used to draw left and right borders. (default: `|`)
+    pub fn border(mut self, border: char) -> Self {
+        self.border = border;
+        self
+    }
+
+    /// The string displayed between columns, not including padding. (default: `"|"`)
+    pub fn divider(mut self, divider: &str) -> Self {
+        self.divider = divider.to_string();
+        self
+    }
+
+    /// Characters for the corners of a bordered table: top left, top right, bottom left, and
+    /// bottom right. (default: none, rules extend to the corners)
+    pub fn corners(mut self, corners: [char; 4]) -> Self {
+        self.corners = Some(corners);
+        self
+    }
+
+    /// Characters for where rules meet dividers and borders: a divider below the rule, the left
+    /// border, dividers above and below, the right border, and a divider above the rule.
+    /// (ex: `['┬', '├', '┼', '┤', '┴']`) (default: none, rules are solid lines)
+    pub fn junctions(mut self, junctions: [char; 5]) -> Self {
+        self.junctions = Some(junctions);
+        self
+    }
+
+    /// Draw rules above and below the table? (default: true)
+    pub fn outer_rules(mut self, outer_rules: bool) -> Self {
+        self.outer_rules = outer_rules;
+        self
+    }
+
+    /// The character used to pad dividers and borders. (default: `' '`)
+    pub fn fill(mut self, fill: char) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    /// Draw a horizontal rule `width` columns wide.
+    pub(crate) fn write_rule(&self, out: &mut String, rule: Rule, width: usize, borders: bool) {
+        let line = if rule == Rule::Header { self.header_rule } else { self.rule };
+        let ends = match (self.corners, rule) {
+            (Some([left, right, _, _]), Rule::Top) if borders => Some((left, right)),
+            (Some([_, _, left, right]), Rule::Bottom) if borders => Some((left, right)),
+            _ => None,
+        };
+
+        match ends {
+            Some((left, right)) if width >= 2 => {
+                out.push(left);
+                out.extend(std::iter::repeat_n(line, width - 2));
+                out.push(right);
+            },
+            _ => out.extend(std::iter::repeat_n(line, width)),
+        }
+    }
+
+    /// Does this theme draw rules of this kind?
+    pub(crate) fn draws(&self, rule: Rule) -> bool {
+        self.outer_rules || !matches!(rule, Rule::Top | Rule::Bottom)
+    }
+
+    /// Write `caption` into a rule, near its start. (ex: `──┤ Cities ├──────`)
+    /// Returns false if there's no room for it.
+    pub(crate) fn caption_rule(&self, line: &mut String, caption: &str) -> bool {
+        // Brackets are the junctions that would join the rule to a border on either side:
+        let (open, close) = match self.junctions {
+            Some([_, left, _, right, _]) => (format!("{} ", right), format!(" {}", left)),
+            None => (" ".to_string(), " ".to_string()),
+        };
+        // Leave a bit of the rule visible at either end:
+        const MARGIN: usize = 2;
+
+        let chars: Vec<char> = line.chars().collect();
+        let fixed = MARGIN * 2 + str_width(&open) + str_width(&close);
+        let available = chars.len().saturating_sub(fixed);
+        if available == 0 {
+            return false;
+        }
+        let caption = printable(caption);
+        let (caption, caption_width) = truncate(&caption, available);
+
+        let mut captioned: String = chars[..MARGIN].iter().collect();
+        captioned.push_str(&open);
+        captioned.push_str(caption);
+        captioned.push_str(&close);
+        captioned.extend(&chars[fixed - MARGIN + caption_width..]);
+        *line = captioned;
+        true
+    }
+
+    pub(crate) fn has_junctions(&self) -> bool {
+        self.junctions.is_some()
+    }
+
+    /// Replace parts of a rule drawn by [`Theme::write_rule`] with junctions, where it meets the
+    /// table's borders and dividers.
+    ///
+    /// `widths` are the widths of the columns, in display order. `above` and `below` say
+    /// whether the lines above and below the rule have dividers.
+    pub(crate) fn join_rule(
+        &self,
+        line: &mut String,
+        rule: Rule,
+        decorations: &Decorations,
+        widths: &[usize],
+        (above, below): (bool, bool),
+    ) {
+        let Some([top, left, cross, right, bottom]) = self.junctions else { return };
+        let inner = match (above, below) {
+            (true, true) => Some(cross),
+            (false, true) => Some(top),
+            (true, false) => Some(bottom),
+            (false, false) => None,
+        };
+        // Top and bottom rules have corners instead, if any:
+        let edges = matches!(rule, Rule::Header | Rule::Middle);
+
+        let mut chars: Vec<char> = line.chars().collect();
+        let mut pos = 0;
+        let mut join = |decoration: &str, junction: Option<char>, pos: &mut usize| {
+            for c in decoration.chars() {
+                let joins = c == self.border || self.divider.contains(c);
+                if let (Some(junction), true, Some(slot)) = (junction, joins, chars.get_mut(*pos)) {
+                    *slot = junction;
+                }
+                *pos += 1;
+            }
+        };
+
+        join(&decorations.left, if edges { Some(left) } else { None }, &mut pos);
+        for (i, width) in widths.iter().enumerate() {
+            if i > 0 {
+                join(&decorations.divider, inner, &mut pos);
+            }
+            pos += width;
+        }
+        join(&decorations.right, if edges { Some(right) } else { None }, &mut pos);
+
+        // If the rule doesn't match the columns, it's better left solid:
+        if pos == chars.len() {
+            *line = chars.into_iter().collect();
+        }
+    }
+}
+
+/// Which parts of a table's frame to draw. See: [`Stream::frame`](crate::Stream::frame).
+///
+/// ```
+/// # use tablestream::Frame;
+/// // Borders around the table, but no lines between columns:
+/// let frame = Frame { outer: true, inner_vertical: false, ..Frame::default() };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    /// Left and right borders. (default: false)
+    pub outer: bool,
+    /// Dividers between columns. Without them, columns are separated by blank space of the
+    /// same width. (default: true)
+    pub inner_vertical: bool,
+    /// The rule between headers and rows. (default: true)
+    pub header_rule: bool,
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        Self {
+            outer: false,
+            inner_vertical: true,
+            header_rule: true,
+        }
+    }
+}
+
+/// Which horizontal rule we're drawing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Rule {
+    Top,
+    Header,
+    Middle,
+    Bottom,
+}
+
+/// Strings for drawing a table, resolved from a [`Theme`] and a Stream's options.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Decorations {
+    pub(crate) left: String,
+    pub(crate) right: String,
+    pub(crate) divider: String,
+}
+
+impl Decorations {
+    pub(crate) fn new(theme: &Theme, frame: Frame, padding: bool, divider: Option<&str>) -> Self {
+        let fill = if padding { theme.fill.to_string() } else { String::new() };
+        let (left, right) = if frame.outer {
+            (format!("{}{}", theme.border, fill), format!("{}{}", fill, theme.border))
+        } else {
+            (String::new(), String::new())
+        };
+        let divider = match divider {
+            Some(divider) => divider.to_string(),
+            None => format!("{}{}{}", fill, theme.divider, fill),
+        };
+        let divider = if frame.inner_vertical {
+            divider
+        } else {
+            theme.fill.to_string().repeat(str_width(&divider))
+        };
+        Self { left, right, divider }
+    }
+}
@@ -0,0 +1,261 @@
+//! Measuring and truncating text by its display width in a terminal.
+//!
+//! Values are normalized to NFC before they're measured and written, so composed and
+//! decomposed forms of the same text (ex: Vietnamese) are output identically.
+//! Combining marks that remain, zero-width joiners, and variation selectors take up no columns.
+//!
+//! Truncation never separates a character from the zero-width marks that follow it:
+//! cutting the end keeps a character's marks with it, and cutting the start drops marks
+//! whose character was cut.
+
+use std::borrow::Cow;
+
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+use unicode_truncate::UnicodeTruncateStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// The number of terminal columns `value` will occupy.
+/// ANSI color/style escape sequences (ex: `\x1b[31m`) take up no columns.
+pub(crate) fn str_width(value: &str) -> usize {
+    // Fast path: Most cells are plain ASCII, where each byte is exactly one column wide.
+    if is_printable_ascii(value) {
+        return value.len();
+    }
+    if value.contains(ESC) {
+        return segments(value).filter(|(_, escape)| !escape).map(|(text, _)| text.width()).sum();
+    }
+    value.width()
+}
+
+/// Replace control characters (ex: newlines, tabs) in `value` with visible placeholders,
+/// since printing them would break the table's layout.
+/// C0 controls become their Unicode "control picture" (ex: `␊`), others become `�`.
+/// ANSI color/style escape sequences are kept, since they don't affect layout.
+///
+/// The result is also normalized to NFC.
+pub(crate) fn printable(value: &str) -> Cow<'_, str> {
+    if is_printable_ascii(value) {
+        return Cow::Borrowed(value);
+    }
+    let value = nfc(value);
+    if !value.chars().any(char::is_control) {
+        return value;
+    }
+
+    let picture = |c: char| match c as u32 {
+        c @ 0x00..=0x1F => char::from_u32(0x2400 + c).unwrap_or(char::REPLACEMENT_CHARACTER),
+        0x7F => '\u{2421}',
+        _ if c.is_control() => char::REPLACEMENT_CHARACTER,
+        _ => c,
+    };
+    let mut printable = String::with_capacity(value.len());
+    for (text, escape) in segments(&value) {
+        if escape {
+            printable.push_str(text);
+        } else {
+            printable.extend(text.chars().map(picture));
+        }
+    }
+    Cow::Owned(printable)
+}
+
+/// `value` in Normalization Form C, with characters and their marks composed where possible.
+fn nfc(value: &str) -> Cow<'_, str> {
+    match is_nfc_quick(value.chars()) {
+        IsNormalized::Yes => Cow::Borrowed(value),
+        _ => Cow::Owned(value.nfc().collect()),
+    }
+}
+
+/// Truncate `value` to at most `width` columns.
+/// Returns the truncated string and its actual display width.
+///
+/// Escape sequences before the cut are kept. See [`escapes`] to find any after it.
+pub(crate) fn truncate(value: &str, width: usize) -> (&str, usize) {
+    if is_printable_ascii(value) {
+        let width = width.min(value.len());
+        return (&value[..width], width);
+    }
+    if !value.contains(ESC) {
+        let (fits, _) = value.unicode_truncate(width);
+        return fit_end(without_dangling_joiner(fits, value), width);
+    }
+
+    let (mut end, mut used) = (0, 0);
+    for (text, escape) in segments(value) {
+        if escape {
+            end += text.len();
+            continue;
+        }
+        let (fits, _) = text.unicode_truncate(width - used);
+        let (fits, fits_width) = fit_end(without_dangling_joiner(fits, text), width - used);
+        end += fits.len();
+        used += fits_width;
+        if fits.len() < text.len() { break; }
+    }
+    (&value[..end], used)
+}
+
+/// Like [`truncate`], but cuts from the start of `value`, keeping its end.
+pub(crate) fn truncate_start(value: &str, width: usize) -> (&str, usize) {
+    if is_printable_ascii(value) {
+        let width = width.min(value.len());
+        return (&value[value.len() - width..], width);
+    }
+    if !value.contains(ESC) {
+        let (fits, _) = value.unicode_truncate_start(width);
+        return fit_start(without_orphaned_marks(fits, value), width);
+    }
+
+    let (mut start, mut used) = (value.len(), 0);
+    for (text, escape) in segments(value).collect::<Vec<_>>().into_iter().rev() {
+        if escape {
+            start -= text.len();
+            continue;
+        }
+        let (fits, _) = text.unicode_truncate_start(width - used);
+        let (fits, fits_width) = fit_start(without_orphaned_marks(fits, text), width - used);
+        start -= fits.len();
+        used += fits_width;
+        if fits.len() < text.len() { break; }
+    }
+    (&value[start..], used)
+}
+
+/// `fits`, shortened from its end until it's at most `width` columns wide, and its width.
+///
+/// `unicode_truncate` adds up the widths of characters, but some sequences are wider than
+/// their characters. (ex: a variation selector makes `🕯` a two-column emoji)
+fn fit_end(mut fits: &str, width: usize) -> (&str, usize) {
+    loop {
+        let fits_width = fits.width();
+        if fits_width <= width { return (fits, fits_width); }
+        // Drop the last character, along with its marks:
+        let last = fits.char_indices().rev().find(|(_, c)| !is_zero_width_mark(*c));
+        fits = &fits[..last.map_or(0, |(i, _)| i)];
+    }
+}
+
+/// Like [`fit_end`], but shortens `fits` from its start.
+fn fit_start(mut fits: &str, width: usize) -> (&str, usize) {
+    loop {
+        let fits_width = fits.width();
+        if fits_width <= width { return (fits, fits_width); }
+        // Drop the first character, along with its marks:
+        let next = fits.char_indices().skip(1).find(|(_, c)| !is_zero_width_mark(*c));
+        fits = &fits[next.map_or(fits.len(), |(i, _)| i)..];
+    }
+}
+
+/// Does `c` attach to the character before it, without taking up any columns of its own?
+/// (ex: combining marks, zero-width joiners, variation selectors)
+fn is_zero_width_mark(c: char) -> bool {
+    !c.is_control() && c.width() == Some(0)
+}
+
+/// `fits`, the start of `text`, without a trailing zero-width joiner that was cut from
+/// the character it joined. (ex: in an emoji sequence)
+fn without_dangling_joiner<'a>(fits: &'a str, text: &str) -> &'a str {
+    if fits.len() == text.len() {
+        return fits;
+    }
+    fits.trim_end_matches('\u{200D}')
+}
+
+/// `fits`, the end of `text`, without leading zero-width marks whose character was cut.
+fn without_orphaned_marks<'a>(fits: &'a str, text: &str) -> &'a str {
+    if fits.len() == text.len() {
+        return fits;
+    }
+    fits.trim_start_matches(is_zero_width_mark)
+}
+
+/// The ANSI escape sequences in `value`. (ex: to keep a reset code that was truncated)
+pub(crate) fn escapes(value: &str) -> impl Iterator<Item = &str> {
+    segments(value).filter(|(_, escape)| *escape).map(|(text, _)| text)
+}
+
+const ESC: char = '\x1b';
+
+/// Split `value` into runs of text and ANSI escape sequences. (`true` for escapes)
+///
+/// Only SGR sequences (colors and styles, ex: `\x1b[1;31m`) are recognized. Other escapes
+/// (ex: cursor movement) would break the table's layout, so they're left as text,
+/// for [`printable`] to neutralize.
+fn segments(value: &str) -> impl Iterator<Item = (&str, bool)> {
+    let mut rest = value;
+    std::iter::from_fn(move || {
+        if rest.is_empty() { return None; }
+
+        let escape_len = sgr_len(rest);
+        let len = match escape_len {
+            Some(len) => len,
+            None => {
+                // Text runs until the next escape sequence:
+                let mut search = 0;
+                loop {
+                    match rest[search..].find(ESC) {
+                        Some(pos) if sgr_len(&rest[search + pos..]).is_some() => break search + pos,
+                        Some(pos) => search += pos + ESC.len_utf8(),
+                        None => break rest.len(),
+                    }
+                }
+            },
+        };
+        let (segment, next) = rest.split_at(len);
+        rest = next;
+        Some((segment, escape_len.is_some()))
+    })
+}
+
+/// The length of the SGR escape sequence at the start of `value`, if there is one.
+fn sgr_len(value: &str) -> Option<usize> {
+    let params = value.strip_prefix("\x1b[")?;
+    let params_len = params.find(|c: char| !(c.is_ascii_digit() || c == ';' || c == ':'))?;
+    match params[params_len..].starts_with('m') {
+        true => Some(2 + params_len + 1),
+        false => None,
+    }
+}
+
+// Control characters (even ASCII ones) have special width rules, so leave them to unicode-width.
+fn is_printable_ascii(value: &str) -> bool {
+    value.bytes().all(|b| (b' '..=b'~').contains(&b))
+}
+
+/// Split `text` into lines that are at most `width` columns wide.
+/// Lines are broken at whitespace where possible, otherwise mid-word.
+pub(crate) fn wrap(text: &str, width: usize) -> Vec<&str> {
+    let mut lines = vec![];
+    let mut rest = text.trim();
+
+    while !rest.is_empty() {
+        if str_width(rest) <= width {
+            lines.push(rest);
+            break;
+        }
+
+        let (mut fits, _) = truncate(rest, width);
+        if fits.is_empty() {
+            // Always make progress, even if the next char is wider than the line:
+            let len = rest.chars().next().map(char::len_utf8).unwrap_or(0);
+            fits = &rest[..len];
+        }
+
+        let next = &rest[fits.len()..];
+        let line_end = if next.starts_with(char::is_whitespace) {
+            fits.len()
+        } else {
+            // Break before the word that didn't fit, if there is one:
+            fits.rfind(char::is_whitespace).unwrap_or(fits.len())
+        };
+
+        lines.push(rest[..line_end].trim_end());
+        rest = rest[line_end..].trim_start();
+    }
+
+    if lines.is_empty() {
+        lines.push("");
+    }
+    lines
+}
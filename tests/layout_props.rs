@@ -0,0 +1,145 @@
+//! Property tests: no combination of columns, options, and cell contents should panic,
+//! and every line of a table should be the same width.
+//!
+//! See also: `fuzz/`, for coverage-guided fuzzing of the same properties.
+
+use std::fmt::Write as _;
+
+use proptest::prelude::*;
+use tablestream::{Column, Degrade, Direction, Stream};
+use unicode_width::UnicodeWidthStr;
+
+type Row = Vec<String>;
+
+#[derive(Debug, Clone)]
+struct ColumnSpec {
+    header: Option<String>,
+    min_width: usize,
+    max_width: Option<usize>,
+    width_pct: Option<f32>,
+    hidden: bool,
+    elide_prefix: bool,
+    alignment: u8,
+    degrade: u8,
+}
+
+#[derive(Debug, Clone)]
+struct Options {
+    max_width: usize,
+    borders: bool,
+    padding: bool,
+    grow: bool,
+    rtl: bool,
+    title: Option<String>,
+    title_wrap: bool,
+    max_height: Option<usize>,
+    footer: Option<String>,
+}
+
+fn column(index: usize, spec: &ColumnSpec) -> Column<Row> {
+    let mut col = Column::new(move |f, row: &Row| {
+        f.write_str(row.get(index).map(String::as_str).unwrap_or(""))
+    }).min_width(spec.min_width);
+
+    if let Some(header) = &spec.header { col = col.header(header); }
+    if let Some(max_width) = spec.max_width { col = col.max_width(max_width); }
+    if let Some(pct) = spec.width_pct { col = col.width_pct(pct); }
+    if spec.hidden { col = col.hidden(); }
+    if spec.elide_prefix { col = col.elide_prefix(); }
+    col = match spec.degrade % 4 {
+        0 => col,
+        1 => col.degrade(&[Degrade::Wrap]),
+        2 => col.degrade(&[Degrade::Wrap, Degrade::Truncate, Degrade::Hide]),
+        _ => col.degrade(&[Degrade::Hide]),
+    };
+    match spec.alignment % 3 {
+        0 => col.left(),
+        1 => col.right(),
+        _ => col.center(),
+    }
+}
+
+fn render(specs: &[ColumnSpec], options: &Options, rows: Vec<Row>) -> std::io::Result<String> {
+    let columns = specs.iter().enumerate().map(|(i, spec)| column(i, spec)).collect();
+    let mut out = Vec::new();
+    let mut stream = Stream::new(&mut out, columns)
+        .max_width(options.max_width)
+        .borders(options.borders)
+        .padding(options.padding)
+        .grow(options.grow)
+        .direction(if options.rtl { Direction::Rtl } else { Direction::Ltr })
+        .title_wrap(options.title_wrap)
+        .strict_layout(true);
+    if let Some(title) = &options.title {
+        stream = stream.title(title);
+    }
+    if let Some(height) = options.max_height {
+        stream = stream.max_height(height);
+    }
+
+    for row in rows {
+        stream.row(row)?;
+    }
+    match &options.footer {
+        Some(footer) => stream.footer(footer)?,
+        None => stream.finish()?,
+    };
+
+    Ok(String::from_utf8(out).expect("valid UTF-8"))
+}
+
+/// Text, including wide characters, combining characters, and control characters.
+fn text() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[a-z ]{0,30}",
+        "[a-zé日本語🦀 ]{0,15}",
+        "\\PC{0,10}",
+        "[a-z\t\n\r\u{7}\u{1b}\u{7f}\u{85} ]{0,10}",
+        any::<String>(),
+    ]
+}
+
+fn column_spec() -> impl Strategy<Value = ColumnSpec> {
+    let widths = (0..20usize, proptest::option::of(0..30usize), proptest::option::weighted(0.2, 0.0..120f32));
+    (proptest::option::of(text()), widths, proptest::bool::weighted(0.1), any::<(bool, u8, u8)>())
+        .prop_map(|(header, (min_width, max_width, width_pct), hidden, (elide_prefix, alignment, degrade))| ColumnSpec {
+            header, min_width, max_width, width_pct, hidden, elide_prefix, alignment, degrade,
+        })
+}
+
+fn options() -> impl Strategy<Value = Options> {
+    (
+        0..120usize,
+        any::<(bool, bool, bool, bool, bool)>(),
+        proptest::option::of(text()),
+        proptest::option::of(0..5usize),
+        proptest::option::of(text()),
+    ).prop_map(|(max_width, (borders, padding, grow, rtl, title_wrap), title, max_height, footer)| Options {
+        max_width, borders, padding, grow, rtl, title, title_wrap, max_height, footer,
+    })
+}
+
+proptest! {
+    #[test]
+    fn lines_have_equal_widths(
+        specs in proptest::collection::vec(column_spec(), 0..8),
+        options in options(),
+        rows in proptest::collection::vec(proptest::collection::vec(text(), 0..8), 0..10),
+    ) {
+        let out = render(&specs, &options, rows).map_err(|e| TestCaseError::fail(e.to_string()))?;
+
+        // Notes may follow the table's last rule, and aren't part of the table:
+        let lines: Vec<_> = out.lines().collect();
+        let table_end = lines.iter().rposition(|l| !l.is_empty() && l.chars().all(|c| c == '-'));
+        let table = &lines[..table_end.map(|i| i + 1).unwrap_or(0)];
+
+        let mut widths = String::new();
+        for line in table {
+            let _ = writeln!(widths, "{:3} {:?}", line.width(), line);
+        }
+        let first = table.first().map(|l| l.width());
+        for line in table {
+            prop_assert_eq!(first, Some(line.width()), "\n{}", widths);
+        }
+    }
+}